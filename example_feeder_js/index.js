@@ -35,7 +35,7 @@ const getConnectedAccount = async () => {
     return await near.account(nearConfig.sender);
 }
 
-const getPricesFromBand = async () => {
+const getPricesFromBand = async (account) => {
     const rawResults = await axios.post(BAND_ENDPOINT, {symbols, min_count:3, ask_count:4}).then(r => r.data['result']);
 
     const result = {
@@ -46,8 +46,10 @@ const getPricesFromBand = async () => {
     }
 
     for ({symbol,multiplier,px,request_id,resolve_time} of rawResults) {
-        if (multiplier !== E9) {
-            throw "multiplier is not equal 1_000_000_000";
+        // the contract normalizes rates with the multiplier registered for the symbol
+        const info = await account.viewFunction(nearConfig.contractName, "get_symbol_info", {symbol});
+        if (!info || info.multiplier !== multiplier) {
+            throw `multiplier of ${symbol} is not registered as ${multiplier}`;
         }
         result.symbols.push(symbol);
         result.rates.push(Number(px));
@@ -64,7 +66,7 @@ const getPricesFromBand = async () => {
     while (true) {
         try {
             console.log("Getting prices from BAND ...")
-            const prices = await getPricesFromBand();
+            const prices = await getPricesFromBand(account);
             console.log(prices);
 
             console.log("Sending relay to NEAR ...")
@@ -1,39 +1,98 @@
 use borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::UnorderedMap;
-use near_sdk::{env, ext_contract, near_bindgen, AccountId};
+use near_sdk::collections::{UnorderedMap, UnorderedSet};
+use near_sdk::json_types::{Base64VecU8, U128, U64};
+use near_sdk::{env, ext_contract, near_bindgen, AccountId, Promise, PromiseResult};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std_reference_interface::errors::Error;
+use std_reference_interface::events::EventStandard;
+use std_reference_interface::ext_std_reference as std_proxy;
+use std_reference_interface::pagination::paginate;
+use std_reference_interface::{admin, upgrade};
+pub use std_reference_interface::ReferenceData;
 
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
-#[ext_contract(std_proxy)]
-pub trait StdProxy {
-    fn get_reference_data(&self, base: String, quote: String) -> Option<(u128, u64, u64)>;
-    fn get_reference_data_bulk(
-        &self,
-        bases: Vec<String>,
-        quotes: Vec<String>,
-    ) -> Option<Vec<(u128, u64, u64)>>;
+const EVENTS: EventStandard = EventStandard { standard: "simple_price_db", version: "1.0.0" };
+pub const STATE_VERSION: u32 = 1;
+
+// Declares the callbacks once so that the bindings used to schedule them and the
+// methods implemented by the contract cannot drift apart. The oracle result is read
+// from the promise results by the callbacks themselves.
+macro_rules! self_callback {
+    ($(#[$attr:meta])* $name:ident) => {
+        $(#[$attr])*
+        pub trait $name {
+            fn callback_set_single(&mut self, symbol: String, requester: AccountId, fee: U128);
+            fn callback_set_multiple(
+                &mut self,
+                symbols: Vec<String>,
+                requester: AccountId,
+                fee: U128,
+            );
+        }
+    };
+}
+
+self_callback!(SelfCallback);
+self_callback!(#[ext_contract(self_callback)] ExtSelfCallback);
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PriceData {
+    pub rate: U128,
+    pub last_updated_base: U64,
+    pub last_updated_quote: U64,
+    pub saved_at: U64,
+    pub saved_by: AccountId,
+}
+
+impl PriceData {
+    fn new(data: ReferenceData, saved_by: AccountId) -> Self {
+        Self {
+            rate: data.rate,
+            last_updated_base: data.last_updated_base,
+            last_updated_quote: data.last_updated_quote,
+            saved_at: env::block_timestamp().into(),
+            saved_by,
+        }
+    }
+
+    // The record with its symbol, as it appears in `save_price` events.
+    fn to_event(&self, symbol: String) -> Value {
+        let mut event = json!(self);
+        event["symbol"] = symbol.into();
+        event
+    }
 }
 
-#[ext_contract(self_callback)]
-pub trait SelfCallback {
-    fn callback_set_single(
-        &self,
-        symbol: String,
-        #[callback]
-        value_opt: Option<(u128, u64, u64)>,
-    );
-    fn callback_set_multiple(
-        &self,
-        symbols: Vec<String>,
-        #[callback]
-        values_opt: Vec<Option<(u128, u64, u64)>>,
-    );
+/// Who may call `set_single` and `set_multiple`. The owner is always allowed.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum AccessPolicy {
+    #[default]
+    OwnerOnly,
+    Allowlist,
+    OpenWithDeposit { fee_per_symbol: U128 },
 }
 
 #[near_bindgen]
 #[derive(Default, BorshDeserialize, BorshSerialize)]
 pub struct SimplePriceDB {
+    pub owner: AccountId,
+    pub oracle: AccountId,
+    pub prices: UnorderedMap<String, PriceData>,
+    pub policy: AccessPolicy,
+    pub allowed_callers: UnorderedSet<AccountId>,
+    pub collected_fees: u128,
+    pub pending_owner: Option<AccountId>,
+    pub guardian: Option<AccountId>,
+    pub paused: bool,
+}
+
+// The unversioned state layout, before prices kept their timestamps and saver.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct OldSimplePriceDB {
     pub owner: AccountId,
     pub oracle: AccountId,
     pub prices: UnorderedMap<String, u128>,
@@ -43,53 +102,191 @@ pub struct SimplePriceDB {
 impl SimplePriceDB {
     #[init]
     pub fn new(oracle: AccountId, owner: AccountId) -> Self {
-        assert!(!env::state_exists(), "ALREADY_INITIALIZED");
-        Self { owner, oracle, prices: UnorderedMap::new(b"prices".to_vec()) }
+        assert!(!env::state_exists(), "{}", Error::AlreadyInitialized);
+        upgrade::set_state_version(STATE_VERSION);
+        Self {
+            owner,
+            oracle,
+            prices: UnorderedMap::new(b"price_data".to_vec()),
+            policy: AccessPolicy::OwnerOnly,
+            allowed_callers: UnorderedSet::new(b"allowed_callers".to_vec()),
+            collected_fees: 0,
+            pending_owner: None,
+            guardian: None,
+            paused: false,
+        }
+    }
+
+    /// See `std_reference_interface::upgrade`. Old prices get zero timestamps; setting stays free.
+    #[init]
+    pub fn migrate() -> Self {
+        let convert = |version| match version {
+            0 => Some(Self::from_unversioned(upgrade::read_state())),
+            STATE_VERSION => Some(upgrade::read_state()),
+            _ => None,
+        };
+        upgrade::migrate(&EVENTS, STATE_VERSION, convert, |contract: &Self| &contract.owner)
+    }
+
+    pub fn get_state_version(&self) -> u32 {
+        upgrade::state_version()
+    }
+
+    /// See `std_reference_interface::upgrade`.
+    pub fn upgrade(&self, code: Base64VecU8) -> Promise {
+        upgrade::upgrade(&EVENTS, &self.owner, code.0)
     }
 
     pub fn get_owner(&self) -> AccountId {
         self.owner.clone()
     }
 
+    pub fn get_pending_owner(&self) -> Option<AccountId> {
+        self.pending_owner.clone()
+    }
+
+    /// See `std_reference_interface::admin::propose_ownership`.
+    pub fn propose_ownership(&mut self, new_owner: AccountId) {
+        admin::propose_ownership(&EVENTS, &self.owner, &mut self.pending_owner, new_owner);
+    }
+
+    pub fn accept_ownership(&mut self) {
+        admin::accept_ownership(&EVENTS, &mut self.owner, &mut self.pending_owner);
+    }
+
+    pub fn cancel_ownership_proposal(&mut self) {
+        admin::cancel_ownership_proposal(&EVENTS, &self.owner, &mut self.pending_owner);
+    }
+
+    pub fn get_guardian(&self) -> Option<AccountId> {
+        self.guardian.clone()
+    }
+
+    /// Sets the account that may pause updates besides the owner.
+    pub fn set_guardian(&mut self, guardian: Option<AccountId>) {
+        admin::set_guardian(&EVENTS, &self.owner, &mut self.guardian, guardian);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Halts `set_single` and `set_multiple`, drops the results of requests already in
+    /// flight, and makes `get_price`, `get_price_data` and `list_prices` fail with `PAUSED`.
+    pub fn pause(&mut self) {
+        admin::pause(&EVENTS, &self.owner, &self.guardian, &mut self.paused);
+    }
+
+    pub fn unpause(&mut self) {
+        admin::unpause(&EVENTS, &self.owner, &mut self.paused);
+    }
+
     pub fn get_oracle(&self) -> AccountId {
         self.oracle.clone()
     }
 
     pub fn set_oracle(&mut self, new_oracle: AccountId) {
-        assert!(env::predecessor_account_id() == self.get_owner(), "NOT_AN_OWNER");
-        env::log(format!("set oracle address from {} to {}", self.oracle, new_oracle).as_bytes());
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        EVENTS.emit("set_oracle", json!({"old_oracle": self.oracle, "new_oracle": new_oracle}));
         self.oracle = new_oracle
     }
 
+    pub fn get_policy(&self) -> AccessPolicy {
+        self.policy.clone()
+    }
+
+    pub fn set_policy(&mut self, policy: AccessPolicy) {
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        EVENTS.emit("set_policy", json!({"old_policy": self.policy, "new_policy": policy}));
+        self.policy = policy;
+    }
+
+    pub fn is_allowed_caller(&self, account: AccountId) -> bool {
+        self.allowed_callers.contains(&account)
+    }
+
+    pub fn get_allowed_callers(&self) -> Vec<AccountId> {
+        self.allowed_callers.to_vec()
+    }
+
+    pub fn add_allowed_caller(&mut self, caller: AccountId) {
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        assert!(self.allowed_callers.insert(&caller), "{}", Error::AlreadyAnAllowedCaller);
+        EVENTS.emit("add_allowed_caller", json!({ "caller": caller }));
+    }
+
+    pub fn remove_allowed_caller(&mut self, caller: AccountId) {
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        assert!(self.allowed_callers.remove(&caller), "{}", Error::NotAnAllowedCaller);
+        EVENTS.emit("remove_allowed_caller", json!({ "caller": caller }));
+    }
+
+    pub fn get_collected_fees(&self) -> U128 {
+        self.collected_fees.into()
+    }
+
+    /// Sends `amount` (all collected fees by default) to the owner.
+    pub fn withdraw_fees(&mut self, amount: Option<U128>) -> Promise {
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        let amount = amount.map(|amount| amount.0).unwrap_or(self.collected_fees);
+        assert!(amount <= self.collected_fees, "{}", Error::NotEnoughCollectedFees);
+        self.collected_fees -= amount;
+        EVENTS.emit("withdraw_fees", json!({"amount": U128(amount), "receiver": self.owner}));
+        Promise::new(self.owner.clone()).transfer(amount)
+    }
+
     pub fn get_price(&self, symbol: String) -> Option<u128> {
+        assert!(!self.paused, "{}", Error::Paused);
+        self.prices.get(&symbol).map(|data| data.rate.0)
+    }
+
+    pub fn get_price_data(&self, symbol: String) -> Option<PriceData> {
+        assert!(!self.paused, "{}", Error::Paused);
         self.prices.get(&symbol)
     }
 
-    pub fn set_single(&self, base: String, quote: String) {
+    /// Lists saved prices in the order their symbols were first saved.
+    pub fn list_prices(&self, from_index: u64, limit: u64) -> Vec<(String, PriceData)> {
+        assert!(!self.paused, "{}", Error::Paused);
+        paginate(&self.prices, from_index, limit)
+    }
+
+    #[payable]
+    pub fn set_single(&mut self, base: String, quote: String) {
+        assert!(!self.paused, "{}", Error::Paused);
+        let fee = self.charge_request(1);
+
         let prepaid_gas = env::prepaid_gas();
         let this = env::current_account_id();
 
         let remaining_gas = prepaid_gas - env::used_gas();
-        std_proxy::get_reference_data(
+        std_proxy::get_reference_data_v2(
             base.clone(),
             quote.clone(),
             &self.oracle,
             0,
             2 * remaining_gas / 5
         ).then(
-            self_callback::callback_set_single(format!("{}/{}",base, quote), &this, 0, 2 * remaining_gas / 5)
+            self_callback::callback_set_single(
+                format!("{}/{}", base, quote),
+                env::predecessor_account_id(),
+                fee.into(),
+                &this,
+                0,
+                2 * remaining_gas / 5,
+            )
         );
     }
 
+    #[payable]
     pub fn set_multiple(
         &mut self,
         bases: Vec<String>,
         quotes: Vec<String>,
     ) {
-        assert!(
-            bases.len() == quotes.len(),
-            format!("BASES_QUOTES_SIZE_IS_NOT_EQUAL:{}!={}",bases.len(),quotes.len())
-        );
+        assert!(bases.len() == quotes.len(), "{}", Error::BadInputLength);
+        assert!(!self.paused, "{}", Error::Paused);
+        let fee = self.charge_request(bases.len());
 
         let prepaid_gas = env::prepaid_gas();
         let this = env::current_account_id();
@@ -99,51 +296,181 @@ impl SimplePriceDB {
         }
 
         let remaining_gas = prepaid_gas - env::used_gas();
-        std_proxy::get_reference_data_bulk(
+        std_proxy::get_reference_data_bulk_partial(
             bases,
             quotes,
             &self.oracle,
             0,
             2 * remaining_gas / 5
         ).then(
-            self_callback::callback_set_multiple(symbols, &this, 0, 2 * remaining_gas / 5)
+            self_callback::callback_set_multiple(
+                symbols,
+                env::predecessor_account_id(),
+                fee.into(),
+                &this,
+                0,
+                2 * remaining_gas / 5,
+            )
         );
     }
+}
 
+#[near_bindgen]
+impl SelfCallback for SimplePriceDB {
     #[result_serializer(borsh)]
-    pub fn callback_set_single(
-        &mut self,
-        symbol: String,
-        #[callback]
-        value_opt: Option<(u128, u64, u64)>,
-    ) {
-        match value_opt {
-            Some((rate, _, _)) => {
-                env::log(format!("Save rate {:?} to state", &rate).as_bytes());
-                self.prices.insert(&symbol, &rate);
-            },
-            None => {
-                env::log(format!("Got None from the oracle").as_bytes());
-            }
+    fn callback_set_single(&mut self, symbol: String, requester: AccountId, fee: U128) {
+        Self::assert_self_call();
+        if self.paused {
+            env::log(Error::Paused.to_string().as_bytes());
+            Self::refund(requester, fee.0);
+            return;
+        }
+        if let Some(data) = self.oracle_result::<ReferenceData>(&requester, fee.0, |_| Ok(())) {
+            let price = PriceData::new(data, requester);
+            self.prices.insert(&symbol, &price);
+            EVENTS.emit("save_price", price.to_event(symbol));
         }
     }
 
     #[result_serializer(borsh)]
-    pub fn callback_set_multiple(
+    fn callback_set_multiple(&mut self, symbols: Vec<String>, requester: AccountId, fee: U128) {
+        Self::assert_self_call();
+        if self.paused {
+            env::log(Error::Paused.to_string().as_bytes());
+            Self::refund(requester, fee.0);
+            return;
+        }
+        // pairs the oracle could not resolve are skipped, the others are saved
+        let check = |values: &Vec<Option<ReferenceData>>| {
+            if values.len() == symbols.len() {
+                Ok(())
+            } else {
+                Err(Error::BadOracleResultLength)
+            }
+        };
+        if let Some(values) = self.oracle_result(&requester, fee.0, check) {
+            let mut saved = vec![];
+            for (symbol, value) in symbols.into_iter().zip(values) {
+                match value {
+                    Some(data) => {
+                        let price = PriceData::new(data, requester.clone());
+                        self.prices.insert(&symbol, &price);
+                        saved.push(price.to_event(symbol));
+                    }
+                    None => {
+                        let error = Error::RefDataNotAvailableFor { symbol };
+                        env::log(error.to_string().as_bytes());
+                    }
+                }
+            }
+            if !saved.is_empty() {
+                EVENTS.emit("save_price", saved.into());
+            }
+        }
+    }
+}
+
+impl SimplePriceDB {
+    // Migrated prices have no known update times, so they are recorded as saved by this
+    // contract at time 0. Anyone could request prices for free before, and still can
+    // until the owner sets another policy.
+    fn from_unversioned(mut old: OldSimplePriceDB) -> Self {
+        let mut prices = UnorderedMap::new(b"price_data".to_vec());
+        for (symbol, rate) in old.prices.iter() {
+            prices.insert(&symbol, &PriceData {
+                rate: rate.into(),
+                last_updated_base: 0.into(),
+                last_updated_quote: 0.into(),
+                saved_at: 0.into(),
+                saved_by: env::current_account_id(),
+            });
+        }
+        old.prices.clear();
+
+        Self {
+            owner: old.owner,
+            oracle: old.oracle,
+            prices,
+            policy: AccessPolicy::OpenWithDeposit { fee_per_symbol: U128(0) },
+            allowed_callers: UnorderedSet::new(b"allowed_callers".to_vec()),
+            collected_fees: 0,
+            pending_owner: None,
+            guardian: None,
+            paused: false,
+        }
+    }
+
+    // Checks that the predecessor may request `symbol_count` prices under the current
+    // policy and returns the fee it is charged. The rest of the attached deposit is sent
+    // back right away, the fee is only collected once the oracle call succeeds.
+    fn charge_request(&mut self, symbol_count: usize) -> u128 {
+        let caller = env::predecessor_account_id();
+        let deposit = env::attached_deposit();
+        let mut fee = 0;
+        if caller != self.owner {
+            match &self.policy {
+                AccessPolicy::OwnerOnly => panic!("{}", Error::NotAnOwner),
+                AccessPolicy::Allowlist => {
+                    assert!(self.allowed_callers.contains(&caller), "{}", Error::NotAnAllowedCaller)
+                }
+                AccessPolicy::OpenWithDeposit { fee_per_symbol } => {
+                    fee = fee_per_symbol.0.saturating_mul(symbol_count as u128);
+                    let required = fee.into();
+                    assert!(
+                        deposit >= fee,
+                        "{}", Error::NotEnoughDeposit { deposit: deposit.into(), required }
+                    );
+                }
+            }
+        }
+        Self::refund(caller, deposit - fee);
+        fee
+    }
+
+    fn refund(receiver: AccountId, amount: u128) {
+        if amount > 0 {
+            EVENTS.emit("refund", json!({"amount": U128(amount), "receiver": receiver}));
+            Promise::new(receiver).transfer(amount);
+        }
+    }
+
+    fn assert_self_call() {
+        let is_self_call = env::predecessor_account_id() == env::current_account_id();
+        assert!(is_self_call, "{}", Error::NotASelfCall);
+    }
+
+    // Reads the `Option<T>` returned by the oracle call this callback is chained on; a
+    // plain `T` parses as well. The requester's `fee` is collected unless the call failed,
+    // its result cannot be decoded or `check` rejects it, in which case the error is
+    // logged, the fee refunded and `None` returned.
+    fn oracle_result<T: DeserializeOwned>(
         &mut self,
-        symbols: Vec<String>,
-        #[callback]
-        values_opt: Option<Vec<(u128, u64, u64)>>,
-    ) {
-        match values_opt {
-            Some(values) => {
-                for (symbol, (rate, _, _)) in symbols.iter().zip(values.iter()) {
-                    self.prices.insert(&symbol, &rate);
+        requester: &AccountId,
+        fee: u128,
+        check: impl FnOnce(&T) -> Result<(), Error>,
+    ) -> Option<T> {
+        assert!(env::promise_results_count() == 1, "{}", Error::BadPromiseResultsCount);
+        let result = match env::promise_result(0) {
+            PromiseResult::Successful(value) => match serde_json::from_slice::<Option<T>>(&value) {
+                Ok(Some(value)) => check(&value).map(|()| Some(value)),
+                Ok(None) => {
+                    env::log(Error::OracleReturnedNone.to_string().as_bytes());
+                    Ok(None)
                 }
-                env::log(format!("Save rates {:?} to state", values).as_bytes());
+                Err(_) => Err(Error::OracleResultUnparsable),
             },
-            None => {
-                env::log(format!("Got None from the oracle").as_bytes());
+            PromiseResult::Failed => Err(Error::OracleCallFailed),
+            PromiseResult::NotReady => panic!("{}", Error::PromiseNotReady),
+        };
+        match result {
+            Ok(value) => {
+                self.collected_fees += fee;
+                value
+            }
+            Err(error) => {
+                Self::refund(requester.clone(), fee);
+                env::log(error.to_string().as_bytes());
+                None
             }
         }
     }
@@ -155,6 +482,7 @@ mod tests {
     use super::*;
     use near_sdk::MockedBlockchain;
     use near_sdk::{testing_env, VMContext};
+    use std_reference_interface::events::{take_emitted, EVENT_JSON_PREFIX};
 
     fn alice() -> AccountId {
         "alice.near".to_string()
@@ -176,6 +504,37 @@ mod tests {
         "another_oracle.near".to_string()
     }
 
+    fn reference_data(rate: u128) -> ReferenceData {
+        ReferenceData::from((rate, 222, 333))
+    }
+
+    fn emitted_events() -> Vec<Value> {
+        take_emitted()
+            .iter()
+            .map(|log| serde_json::from_str(&log[EVENT_JSON_PREFIX.len()..]).unwrap())
+            .collect()
+    }
+
+    fn successful<T: serde::Serialize>(value: Option<T>) -> PromiseResult {
+        PromiseResult::Successful(serde_json::to_vec(&value).unwrap())
+    }
+
+    // Like `testing_env!`, but the callback sees `promise_result` as the result of its promise.
+    fn set_promise_result(context: VMContext, promise_result: PromiseResult) {
+        let storage = match env::take_blockchain_interface() {
+            Some(mut bi) => bi.as_mut_mocked_blockchain().unwrap().take_storage(),
+            None => Default::default(),
+        };
+        env::set_blockchain_interface(Box::new(MockedBlockchain::new(
+            context,
+            Default::default(),
+            Default::default(),
+            vec![promise_result],
+            storage,
+            Default::default(),
+        )));
+    }
+
     fn get_context() -> VMContext {
         VMContext {
             current_account_id: alice(),
@@ -212,6 +571,28 @@ mod tests {
         assert_eq!(alice(), contract.get_owner());
     }
 
+    // The transfer itself is tested in `std_ref_interface::admin`.
+    #[test]
+    fn test_transfer_ownership() {
+        let mut context = get_context();
+        context.predecessor_account_id = alice();
+        testing_env!(context.clone());
+        let mut contract = SimplePriceDB::new(std_proxy(), alice());
+
+        contract.propose_ownership(bob());
+        contract.cancel_ownership_proposal();
+        assert_eq!(None, contract.get_pending_owner());
+        contract.propose_ownership(bob());
+        assert_eq!(Some(bob()), contract.get_pending_owner());
+
+        context.predecessor_account_id = bob();
+        context.storage_usage = env::storage_usage();
+        testing_env!(context);
+        contract.accept_ownership();
+        assert_eq!(bob(), contract.get_owner());
+        assert_eq!(None, contract.get_pending_owner());
+    }
+
     #[test]
     #[should_panic(expected = "NOT_AN_OWNER")]
     fn test_set_oracle_fail() {
@@ -229,7 +610,577 @@ mod tests {
         testing_env!(context);
         let mut contract = SimplePriceDB::new(std_proxy(), alice());
 
-        contract.set_oracle(another_oracle())
+        contract.set_oracle(another_oracle());
+
+        assert_eq!(
+            vec![concat!(
+                r#"EVENT_JSON:{"standard":"simple_price_db","version":"1.0.0","event":"set_oracle","#,
+                r#""data":[{"old_oracle":"std_proxy.near","new_oracle":"another_oracle.near"}]}"#,
+            )],
+            take_emitted()
+        );
+    }
+
+    #[test]
+    fn test_callback_set_single() {
+        let mut context = get_context();
+        context.predecessor_account_id = alice();
+        testing_env!(context.clone());
+        let mut contract = SimplePriceDB::new(std_proxy(), alice());
+
+        set_promise_result(context.clone(), successful(Some(reference_data(111))));
+        contract.callback_set_single("BTC/USD".into(), bob(), U128(0));
+
+        set_promise_result(context, successful(None::<ReferenceData>));
+        contract.callback_set_single("ETH/USD".into(), bob(), U128(0));
+
+        assert_eq!(Some(111), contract.get_price("BTC/USD".into()));
+        assert_eq!(None, contract.get_price("ETH/USD".into()));
+    }
+
+    #[test]
+    fn test_callback_set_single_and_get_price_data() {
+        let mut context = get_context();
+        context.predecessor_account_id = alice();
+        context.block_timestamp = 777;
+        testing_env!(context.clone());
+        let mut contract = SimplePriceDB::new(std_proxy(), alice());
+
+        set_promise_result(context, successful(Some(reference_data(111))));
+        contract.callback_set_single("BTC/USD".into(), bob(), U128(0));
+
+        assert_eq!(
+            Some(PriceData {
+                rate: U128(111),
+                last_updated_base: U64(222),
+                last_updated_quote: U64(333),
+                saved_at: U64(777),
+                saved_by: bob(),
+            }),
+            contract.get_price_data("BTC/USD".into())
+        );
+        assert_eq!(None, contract.get_price_data("ETH/USD".into()));
+    }
+
+    #[test]
+    fn test_callback_set_single_oracle_failed() {
+        let mut context = get_context();
+        context.predecessor_account_id = alice();
+        testing_env!(context.clone());
+        let mut contract = SimplePriceDB::new(std_proxy(), alice());
+
+        set_promise_result(context.clone(), PromiseResult::Failed);
+        contract.callback_set_single("BTC/USD".into(), bob(), U128(0));
+
+        set_promise_result(context, PromiseResult::Successful(b"not json".to_vec()));
+        contract.callback_set_single("ETH/USD".into(), bob(), U128(0));
+
+        assert_eq!(None, contract.get_price("BTC/USD".into()));
+        assert_eq!(None, contract.get_price("ETH/USD".into()));
+    }
+
+    #[test]
+    #[should_panic(expected = "BAD_PROMISE_RESULTS_COUNT")]
+    fn test_callback_set_single_without_promise_result() {
+        let mut context = get_context();
+        context.predecessor_account_id = alice();
+        testing_env!(context);
+        let mut contract = SimplePriceDB::new(std_proxy(), alice());
+
+        contract.callback_set_single("BTC/USD".into(), bob(), U128(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_A_SELF_CALL")]
+    fn test_callback_set_single_fail_because_forged() {
+        let context = get_context();
+        testing_env!(context.clone());
+        let mut contract = SimplePriceDB::new(std_proxy(), alice());
+
+        // carol calls the callback directly with a made up oracle result
+        set_promise_result(context, successful(Some(reference_data(1))));
+        contract.callback_set_single("BTC/USD".into(), bob(), U128(0));
+    }
+
+    #[test]
+    fn test_callback_set_multiple() {
+        let mut context = get_context();
+        context.predecessor_account_id = alice();
+        testing_env!(context.clone());
+        let mut contract = SimplePriceDB::new(std_proxy(), alice());
+
+        set_promise_result(
+            context,
+            successful(Some(vec![reference_data(111), reference_data(444)])),
+        );
+        contract.callback_set_multiple(vec!["BTC/USD".into(), "ETH/USD".into()], bob(), U128(0));
+
+        assert_eq!(Some(111), contract.get_price("BTC/USD".into()));
+        assert_eq!(Some(444), contract.get_price("ETH/USD".into()));
+    }
+
+    #[test]
+    fn test_list_prices() {
+        let mut context = get_context();
+        context.predecessor_account_id = alice();
+        testing_env!(context.clone());
+        let mut contract = SimplePriceDB::new(std_proxy(), alice());
+        assert_eq!(Vec::<(String, PriceData)>::new(), contract.list_prices(0, 10));
+
+        set_promise_result(
+            context.clone(),
+            successful(Some(vec![reference_data(111), reference_data(444)])),
+        );
+        contract.callback_set_multiple(vec!["BTC/USD".into(), "ETH/USD".into()], bob(), U128(0));
+        context.storage_usage = env::storage_usage();
+        set_promise_result(context, successful(Some(reference_data(222))));
+        contract.callback_set_single("BTC/USD".into(), bob(), U128(0));
+
+        let price = |rate: u128| PriceData::new(reference_data(rate), bob());
+        assert_eq!(
+            vec![("BTC/USD".to_string(), price(222)), ("ETH/USD".to_string(), price(444))],
+            contract.list_prices(0, 10)
+        );
+        assert_eq!(vec![("ETH/USD".to_string(), price(444))], contract.list_prices(1, 10));
+        assert_eq!(vec![("BTC/USD".to_string(), price(222))], contract.list_prices(0, 1));
+        assert_eq!(Vec::<(String, PriceData)>::new(), contract.list_prices(2, u64::MAX));
+    }
+
+    #[test]
+    fn test_callback_set_multiple_partial() {
+        let mut context = get_context();
+        context.predecessor_account_id = alice();
+        testing_env!(context.clone());
+        let mut contract = SimplePriceDB::new(std_proxy(), alice());
+
+        set_promise_result(
+            context,
+            successful(Some(vec![Some(reference_data(111)), None, Some(reference_data(444))])),
+        );
+        contract.callback_set_multiple(
+            vec!["BTC/USD".into(), "DOGE/USD".into(), "ETH/USD".into()],
+            bob(),
+            U128(0),
+        );
+
+        assert_eq!(Some(111), contract.get_price("BTC/USD".into()));
+        assert_eq!(None, contract.get_price("DOGE/USD".into()));
+        assert_eq!(Some(444), contract.get_price("ETH/USD".into()));
+
+        // one event for all the saved pairs, none for the unresolved one
+        let saved = |symbol: &str, rate: &str| {
+            json!({
+                "symbol": symbol,
+                "rate": rate,
+                "last_updated_base": "222",
+                "last_updated_quote": "333",
+                "saved_at": "0",
+                "saved_by": "bob.near",
+            })
+        };
+        assert_eq!(
+            vec![json!({
+                "standard": "simple_price_db",
+                "version": "1.0.0",
+                "event": "save_price",
+                "data": [saved("BTC/USD", "111"), saved("ETH/USD", "444")],
+            })],
+            emitted_events()
+        );
     }
 
+    #[test]
+    fn test_callback_set_multiple_oracle_failed() {
+        let mut context = get_context();
+        context.predecessor_account_id = alice();
+        testing_env!(context.clone());
+        let mut contract = SimplePriceDB::new(std_proxy(), alice());
+
+        set_promise_result(context, PromiseResult::Failed);
+        contract.callback_set_multiple(vec!["BTC/USD".into(), "ETH/USD".into()], bob(), U128(0));
+
+        assert_eq!(None, contract.get_price("BTC/USD".into()));
+        assert_eq!(None, contract.get_price("ETH/USD".into()));
+    }
+
+    #[test]
+    fn test_callback_set_multiple_refunds_fee_when_result_length_mismatches() {
+        let mut context = get_context();
+        context.predecessor_account_id = alice();
+        context.account_balance = 1_000;
+        testing_env!(context.clone());
+        let mut contract = SimplePriceDB::new(std_proxy(), alice());
+
+        set_promise_result(context, successful(Some(vec![Some(reference_data(111))])));
+        contract.callback_set_multiple(vec!["BTC/USD".into(), "ETH/USD".into()], carol(), U128(10));
+
+        assert_eq!(None, contract.get_price("BTC/USD".into()));
+        assert_eq!(U128(0), contract.get_collected_fees());
+        assert_eq!(
+            vec![json!({
+                "standard": "simple_price_db",
+                "version": "1.0.0",
+                "event": "refund",
+                "data": [{"amount": "10", "receiver": "carol.near"}],
+            })],
+            emitted_events()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_A_SELF_CALL")]
+    fn test_callback_set_multiple_fail_because_forged() {
+        let context = get_context();
+        testing_env!(context.clone());
+        let mut contract = SimplePriceDB::new(std_proxy(), alice());
+
+        set_promise_result(context, successful(Some(vec![reference_data(1)])));
+        contract.callback_set_multiple(vec!["BTC/USD".into()], bob(), U128(0));
+    }
+
+    #[test]
+    fn test_implements_self_callback() {
+        fn assert_self_callback<T: SelfCallback>() {}
+        assert_self_callback::<SimplePriceDB>();
+    }
+
+    #[test]
+    fn test_migrate() {
+        let mut context = get_context();
+        context.predecessor_account_id = alice();
+        testing_env!(context.clone());
+
+        let mut old_prices = UnorderedMap::new(b"prices".to_vec());
+        old_prices.insert(&"BTC/USD".to_string(), &111u128);
+        old_prices.insert(&"ETH/USD".to_string(), &222u128);
+        env::state_write(&OldSimplePriceDB { owner: bob(), oracle: std_proxy(), prices: old_prices });
+
+        // the contract account itself may migrate right after deploying the new code
+        let contract = SimplePriceDB::migrate();
+
+        assert_eq!(bob(), contract.get_owner());
+        assert_eq!(std_proxy(), contract.get_oracle());
+        assert_eq!(Some(111), contract.get_price("BTC/USD".into()));
+        assert_eq!(Some(222), contract.get_price("ETH/USD".into()));
+        assert_eq!(
+            Some(PriceData {
+                rate: U128(111),
+                last_updated_base: U64(0),
+                last_updated_quote: U64(0),
+                saved_at: U64(0),
+                saved_by: alice(),
+            }),
+            contract.get_price_data("BTC/USD".into())
+        );
+
+        assert_eq!(STATE_VERSION, contract.get_state_version());
+        assert_eq!(
+            AccessPolicy::OpenWithDeposit { fee_per_symbol: U128(0) },
+            contract.get_policy()
+        );
+
+        // the old map has been cleared
+        let old_prices: UnorderedMap<String, u128> = UnorderedMap::new(b"prices".to_vec());
+        assert!(old_prices.is_empty());
+    }
+
+    #[test]
+    fn test_set_single_as_anyone_after_migrating() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context.clone());
+        let prices = UnorderedMap::new(b"prices".to_vec());
+        env::state_write(&OldSimplePriceDB { owner: bob(), oracle: std_proxy(), prices });
+        let mut contract = SimplePriceDB::migrate();
+
+        context.predecessor_account_id = carol();
+        testing_env!(context);
+        contract.set_single("BTC".into(), "USD".into());
+    }
+
+    #[test]
+    #[should_panic(expected = "UNSUPPORTED_STATE_VERSION:2")]
+    fn test_migrate_fail_because_unsupported_version() {
+        let mut context = get_context();
+        context.predecessor_account_id = alice();
+        testing_env!(context);
+        env::state_write(&SimplePriceDB::new(std_proxy(), alice()));
+        upgrade::set_state_version(2);
+
+        SimplePriceDB::migrate();
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_upgrade_fail_because_not_owner() {
+        let context = get_context();
+        testing_env!(context);
+        let contract = SimplePriceDB::new(std_proxy(), alice());
+
+        contract.upgrade(Base64VecU8(b"code".to_vec()));
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_migrate_fail_because_not_owner() {
+        let context = get_context();
+        testing_env!(context);
+
+        env::state_write(&OldSimplePriceDB {
+            owner: bob(),
+            oracle: std_proxy(),
+            prices: UnorderedMap::new(b"prices".to_vec()),
+        });
+
+        SimplePriceDB::migrate();
+    }
+
+    #[test]
+    fn test_set_single_and_set_multiple_by_owner() {
+        let mut context = get_context();
+        context.predecessor_account_id = alice();
+        testing_env!(context);
+        let mut contract = SimplePriceDB::new(std_proxy(), alice());
+
+        assert_eq!(AccessPolicy::OwnerOnly, contract.get_policy());
+        contract.set_single("BTC".into(), "USD".into());
+        contract.set_multiple(vec!["BTC".into(), "ETH".into()], vec!["USD".into(), "USD".into()]);
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_set_single_fail_because_not_owner() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = SimplePriceDB::new(std_proxy(), alice());
+
+        contract.set_single("BTC".into(), "USD".into());
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_set_policy_fail_because_not_owner() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = SimplePriceDB::new(std_proxy(), alice());
+
+        contract.set_policy(AccessPolicy::Allowlist);
+    }
+
+    #[test]
+    fn test_set_multiple_by_allowed_caller() {
+        let mut context = get_context();
+        context.predecessor_account_id = alice();
+        testing_env!(context.clone());
+        let mut contract = SimplePriceDB::new(std_proxy(), alice());
+
+        contract.set_policy(AccessPolicy::Allowlist);
+        contract.add_allowed_caller(carol());
+        assert!(contract.is_allowed_caller(carol()));
+        assert_eq!(vec![carol()], contract.get_allowed_callers());
+
+        context.predecessor_account_id = carol();
+        testing_env!(context);
+        contract.set_multiple(vec!["BTC".into(), "ETH".into()], vec!["USD".into(), "USD".into()]);
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_ALLOWED_CALLER")]
+    fn test_set_single_fail_because_not_allowed_caller() {
+        let mut context = get_context();
+        context.predecessor_account_id = alice();
+        testing_env!(context.clone());
+        let mut contract = SimplePriceDB::new(std_proxy(), alice());
+
+        contract.set_policy(AccessPolicy::Allowlist);
+        contract.add_allowed_caller(carol());
+        contract.remove_allowed_caller(carol());
+        assert!(!contract.is_allowed_caller(carol()));
+
+        context.predecessor_account_id = carol();
+        testing_env!(context);
+        contract.set_single("BTC".into(), "USD".into());
+    }
+
+    #[test]
+    fn test_set_multiple_with_deposit_and_withdraw_fees() {
+        let mut context = get_context();
+        context.predecessor_account_id = alice();
+        context.account_balance = 1_000;
+        testing_env!(context.clone());
+        let mut contract = SimplePriceDB::new(std_proxy(), alice());
+
+        let policy = AccessPolicy::OpenWithDeposit { fee_per_symbol: U128(10) };
+        contract.set_policy(policy.clone());
+        assert_eq!(policy, contract.get_policy());
+
+        context.predecessor_account_id = carol();
+        context.attached_deposit = 20;
+        testing_env!(context.clone());
+        contract.set_multiple(vec!["BTC".into(), "ETH".into()], vec!["USD".into(), "USD".into()]);
+        assert_eq!(U128(0), contract.get_collected_fees());
+
+        // the fee is collected once the oracle answers
+        context.predecessor_account_id = alice();
+        set_promise_result(context.clone(), successful(Some(vec![Some(reference_data(111))])));
+        contract.callback_set_multiple(vec!["BTC/USD".into()], carol(), U128(20));
+        assert_eq!(U128(20), contract.get_collected_fees());
+
+        context.predecessor_account_id = alice();
+        context.attached_deposit = 0;
+        testing_env!(context);
+        contract.withdraw_fees(Some(U128(5)));
+        assert_eq!(U128(15), contract.get_collected_fees());
+        contract.withdraw_fees(None);
+        assert_eq!(U128(0), contract.get_collected_fees());
+    }
+
+    #[test]
+    fn test_set_single_refunds_overpayment() {
+        let mut context = get_context();
+        context.predecessor_account_id = alice();
+        context.account_balance = 1_000;
+        testing_env!(context.clone());
+        let mut contract = SimplePriceDB::new(std_proxy(), alice());
+        contract.set_policy(AccessPolicy::OpenWithDeposit { fee_per_symbol: U128(10) });
+        take_emitted();
+
+        context.predecessor_account_id = carol();
+        context.attached_deposit = 25;
+        testing_env!(context);
+        contract.set_single("BTC".into(), "USD".into());
+
+        assert_eq!(
+            vec![json!({
+                "standard": "simple_price_db",
+                "version": "1.0.0",
+                "event": "refund",
+                "data": [{"amount": "15", "receiver": "carol.near"}],
+            })],
+            emitted_events()
+        );
+    }
+
+    #[test]
+    fn test_callback_refunds_fee_when_oracle_call_failed() {
+        let mut context = get_context();
+        context.predecessor_account_id = alice();
+        context.account_balance = 1_000;
+        testing_env!(context.clone());
+        let mut contract = SimplePriceDB::new(std_proxy(), alice());
+
+        set_promise_result(context.clone(), PromiseResult::Failed);
+        contract.callback_set_single("BTC/USD".into(), carol(), U128(10));
+        assert_eq!(U128(0), contract.get_collected_fees());
+        assert_eq!(
+            vec![json!({
+                "standard": "simple_price_db",
+                "version": "1.0.0",
+                "event": "refund",
+                "data": [{"amount": "10", "receiver": "carol.near"}],
+            })],
+            emitted_events()
+        );
+
+        // a call that succeeds without a price still collects the fee
+        set_promise_result(context, successful(None::<ReferenceData>));
+        contract.callback_set_single("BTC/USD".into(), carol(), U128(10));
+        assert_eq!(U128(10), contract.get_collected_fees());
+        assert_eq!(Vec::<Value>::new(), emitted_events());
+    }
+
+    #[test]
+    fn test_callback_refunds_fee_when_oracle_result_unparsable() {
+        let mut context = get_context();
+        context.predecessor_account_id = alice();
+        context.account_balance = 1_000;
+        testing_env!(context.clone());
+        let mut contract = SimplePriceDB::new(std_proxy(), alice());
+
+        set_promise_result(context, PromiseResult::Successful(b"not json".to_vec()));
+        contract.callback_set_single("BTC/USD".into(), carol(), U128(10));
+
+        assert_eq!(None, contract.get_price("BTC/USD".into()));
+        assert_eq!(U128(0), contract.get_collected_fees());
+        assert_eq!(
+            vec![json!({
+                "standard": "simple_price_db",
+                "version": "1.0.0",
+                "event": "refund",
+                "data": [{"amount": "10", "receiver": "carol.near"}],
+            })],
+            emitted_events()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_ENOUGH_DEPOSIT")]
+    fn test_set_multiple_fail_because_not_enough_deposit() {
+        let mut context = get_context();
+        context.predecessor_account_id = alice();
+        testing_env!(context.clone());
+        let mut contract = SimplePriceDB::new(std_proxy(), alice());
+
+        contract.set_policy(AccessPolicy::OpenWithDeposit { fee_per_symbol: U128(10) });
+
+        context.predecessor_account_id = carol();
+        context.attached_deposit = 19;
+        testing_env!(context);
+        contract.set_multiple(vec!["BTC".into(), "ETH".into()], vec!["USD".into(), "USD".into()]);
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_withdraw_fees_fail_because_not_owner() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = SimplePriceDB::new(std_proxy(), alice());
+
+        contract.withdraw_fees(None);
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_ENOUGH_COLLECTED_FEES")]
+    fn test_withdraw_fees_fail_because_not_enough_collected_fees() {
+        let mut context = get_context();
+        context.predecessor_account_id = alice();
+        testing_env!(context);
+        let mut contract = SimplePriceDB::new(std_proxy(), alice());
+
+        contract.withdraw_fees(Some(U128(1)));
+    }
+
+    // Pausing itself is tested in `std_ref_interface::admin`.
+    #[test]
+    fn test_pause_drops_results_in_flight() {
+        let mut context = get_context();
+        context.predecessor_account_id = alice();
+        testing_env!(context.clone());
+        let mut contract = SimplePriceDB::new(std_proxy(), alice());
+        contract.set_guardian(Some(carol()));
+
+        context.predecessor_account_id = carol();
+        testing_env!(context.clone());
+        contract.pause();
+        assert!(contract.is_paused());
+
+        context.predecessor_account_id = alice();
+        set_promise_result(context, successful(Some(reference_data(111))));
+        contract.callback_set_single("BTC/USD".into(), bob(), U128(0));
+        contract.unpause();
+
+        assert_eq!(None, contract.get_price("BTC/USD".into()));
+    }
+
+    #[test]
+    #[should_panic(expected = "PAUSED")]
+    fn test_set_single_fail_because_paused() {
+        let mut context = get_context();
+        context.predecessor_account_id = alice();
+        testing_env!(context);
+        let mut contract = SimplePriceDB::new(std_proxy(), alice());
+        contract.pause();
+
+        contract.set_single("BTC".into(), "USD".into());
+    }
 }
@@ -1,45 +1,112 @@
 use borsh::{BorshDeserialize, BorshSerialize};
-use near_sdk::{env, ext_contract, near_bindgen, AccountId, Promise};
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::{env, near_bindgen, AccountId, Promise};
+use serde_json::json;
+use std_reference_interface::errors::Error;
+use std_reference_interface::events::EventStandard;
+use std_reference_interface::ext_std_reference as ext;
+use std_reference_interface::{admin, upgrade};
+pub use std_reference_interface::{ReferenceData, StdReferenceProxy};
 
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
-#[ext_contract(ext)]
-pub trait StdRef {
-    fn get_reference_data(&self, base: String, quote: String) -> Option<(u128, u64, u64)>;
-    fn get_reference_data_bulk(
-        &self,
-        bases: Vec<String>,
-        quotes: Vec<String>,
-    ) -> Option<Vec<(u128, u64, u64)>>;
-}
+const EVENTS: EventStandard = EventStandard { standard: "std_proxy", version: "1.0.0" };
+pub const STATE_VERSION: u32 = 1;
 
 #[near_bindgen]
 #[derive(Default, BorshDeserialize, BorshSerialize)]
 pub struct StdProxy {
     pub ref_: AccountId,
     pub owner: AccountId,
+    pub pending_owner: Option<AccountId>,
+    pub guardian: Option<AccountId>,
+    pub paused: bool,
+}
+
+// The unversioned state layout, before two-step ownership transfers and pausing.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct OldStdProxy {
+    pub ref_: AccountId,
+    pub owner: AccountId,
 }
 
 #[near_bindgen]
 impl StdProxy {
     #[init]
     pub fn new(ref_: AccountId) -> Self {
-        assert!(!env::state_exists(), "ALREADY_INITIALIZED");
+        assert!(!env::state_exists(), "{}", Error::AlreadyInitialized);
+        upgrade::set_state_version(STATE_VERSION);
         Self {
-            ref_: ref_,
+            ref_,
             owner: env::signer_account_id(),
+            pending_owner: None,
+            guardian: None,
+            paused: false,
         }
     }
 
+    /// See `std_reference_interface::upgrade`. Old states start unpaused, with no guardian.
+    #[init]
+    pub fn migrate() -> Self {
+        let convert = |version| match version {
+            0 => Some(Self::from_unversioned(upgrade::read_state())),
+            STATE_VERSION => Some(upgrade::read_state()),
+            _ => None,
+        };
+        upgrade::migrate(&EVENTS, STATE_VERSION, convert, |contract: &Self| &contract.owner)
+    }
+
+    pub fn get_state_version(&self) -> u32 {
+        upgrade::state_version()
+    }
+
+    /// See `std_reference_interface::upgrade`.
+    pub fn upgrade(&self, code: Base64VecU8) -> Promise {
+        upgrade::upgrade(&EVENTS, &self.owner, code.0)
+    }
+
     pub fn get_owner(&self) -> AccountId {
         self.owner.clone()
     }
 
-    pub fn transfer_ownership(&mut self, new_owner: AccountId) {
-        assert!(env::predecessor_account_id() == self.get_owner(), "NOT_AN_OWNER");
-        env::log(format!("transfer ownership from {} to {}", self.owner, new_owner).as_bytes());
-        self.owner = new_owner;
+    pub fn get_pending_owner(&self) -> Option<AccountId> {
+        self.pending_owner.clone()
+    }
+
+    /// See `std_reference_interface::admin::propose_ownership`.
+    pub fn propose_ownership(&mut self, new_owner: AccountId) {
+        admin::propose_ownership(&EVENTS, &self.owner, &mut self.pending_owner, new_owner);
+    }
+
+    pub fn accept_ownership(&mut self) {
+        admin::accept_ownership(&EVENTS, &mut self.owner, &mut self.pending_owner);
+    }
+
+    pub fn cancel_ownership_proposal(&mut self) {
+        admin::cancel_ownership_proposal(&EVENTS, &self.owner, &mut self.pending_owner);
+    }
+
+    pub fn get_guardian(&self) -> Option<AccountId> {
+        self.guardian.clone()
+    }
+
+    /// Sets the account that may pause forwarding besides the owner.
+    pub fn set_guardian(&mut self, guardian: Option<AccountId>) {
+        admin::set_guardian(&EVENTS, &self.owner, &mut self.guardian, guardian);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Halts forwarding: every `get_reference_data*` call fails with `PAUSED`.
+    pub fn pause(&mut self) {
+        admin::pause(&EVENTS, &self.owner, &self.guardian, &mut self.paused);
+    }
+
+    pub fn unpause(&mut self) {
+        admin::unpause(&EVENTS, &self.owner, &mut self.paused);
     }
 
     pub fn get_ref(&self) -> AccountId {
@@ -47,26 +114,65 @@ impl StdProxy {
     }
 
     pub fn set_ref(&mut self, new_ref: AccountId) {
-        assert!(env::predecessor_account_id() == self.get_owner(), "NOT_AN_OWNER");
-        env::log(format!("set ref from {} to {}", self.ref_, new_ref).as_bytes());
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        EVENTS.emit("set_ref", json!({"old_ref": self.ref_, "new_ref": new_ref}));
         self.ref_ = new_ref
     }
+}
+
+impl StdProxy {
+    fn from_unversioned(old: OldStdProxy) -> Self {
+        Self { ref_: old.ref_, owner: old.owner, pending_owner: None, guardian: None, paused: false }
+    }
+}
 
-    pub fn get_reference_data(
+#[near_bindgen]
+impl StdReferenceProxy for StdProxy {
+    fn get_reference_data(
         &mut self,
         base: String,
         quote: String,
     ) -> Promise {
+        assert!(!self.paused, "{}", Error::Paused);
         ext::get_reference_data(base, quote, &self.ref_, 0, 9 * env::prepaid_gas() / 10)
     }
 
-    pub fn get_reference_data_bulk(
+    fn get_reference_data_bulk(
         &mut self,
         bases: Vec<String>,
         quotes: Vec<String>,
     ) -> Promise {
+        assert!(!self.paused, "{}", Error::Paused);
         ext::get_reference_data_bulk(bases, quotes, &self.ref_, 0, 9 * env::prepaid_gas() / 10)
     }
+
+    fn get_reference_data_v2(
+        &mut self,
+        base: String,
+        quote: String,
+    ) -> Promise {
+        assert!(!self.paused, "{}", Error::Paused);
+        ext::get_reference_data_v2(base, quote, &self.ref_, 0, 9 * env::prepaid_gas() / 10)
+    }
+
+    fn get_reference_data_bulk_v2(
+        &mut self,
+        bases: Vec<String>,
+        quotes: Vec<String>,
+    ) -> Promise {
+        assert!(!self.paused, "{}", Error::Paused);
+        ext::get_reference_data_bulk_v2(bases, quotes, &self.ref_, 0, 9 * env::prepaid_gas() / 10)
+    }
+
+    fn get_reference_data_bulk_partial(
+        &mut self,
+        bases: Vec<String>,
+        quotes: Vec<String>,
+    ) -> Promise {
+        assert!(!self.paused, "{}", Error::Paused);
+        let gas = 9 * env::prepaid_gas() / 10;
+        ext::get_reference_data_bulk_partial(bases, quotes, &self.ref_, 0, gas)
+    }
 }
 
 // use the attribute below for unit tests
@@ -74,6 +180,7 @@ impl StdProxy {
 mod tests {
     use super::*;
     use near_sdk::MockedBlockchain;
+    use std_reference_interface::events::take_emitted;
     use near_sdk::{testing_env, VMContext};
 
     fn alice() -> AccountId {
@@ -127,51 +234,61 @@ mod tests {
         assert_eq!(std_basic(), contract.get_ref());
     }
 
+    // The transfer itself is tested in `std_ref_interface::admin`.
     #[test]
     fn test_transfer_ownership() {
         let mut context = get_context();
         context.predecessor_account_id = bob();
-
-        testing_env!(context);
+        testing_env!(context.clone());
         let mut contract = StdProxy::new(std_basic());
 
-        // check owner using view function
-        assert_eq!(bob(), contract.get_owner());
-        // transfer the ownership bob -> alice
-        contract.transfer_ownership(alice());
-        // check owner using view function
+        contract.propose_ownership(alice());
+        contract.cancel_ownership_proposal();
+        assert_eq!(None, contract.get_pending_owner());
+        contract.propose_ownership(alice());
+        assert_eq!(Some(alice()), contract.get_pending_owner());
+
+        context.predecessor_account_id = alice();
+        context.storage_usage = env::storage_usage();
+        testing_env!(context);
+        contract.accept_ownership();
         assert_eq!(alice(), contract.get_owner());
+        assert_eq!(None, contract.get_pending_owner());
     }
 
     #[test]
-    #[should_panic(expected = "NOT_AN_OWNER")]
-    fn test_transfer_ownership_fail() {
+    fn test_set_ref() {
         let mut context = get_context();
         context.predecessor_account_id = bob();
 
         testing_env!(context);
         let mut contract = StdProxy::new(std_basic());
 
-        // check owner using view function
-        assert_eq!(bob(), contract.get_owner());
-        // transfer the ownership bob -> alice
-        contract.transfer_ownership(alice());
-        // check owner using view function
-        assert_eq!(alice(), contract.get_owner());
+        assert_eq!(std_basic(), contract.get_ref());
 
-        // transfer the ownership alice -> bob
-        // should fail because bob is not an owner anymore
-        contract.transfer_ownership(alice());
+        contract.set_ref(alice());
+
+        assert_eq!(alice(), contract.get_ref());
+        assert_eq!(
+            vec![concat!(
+                r#"EVENT_JSON:{"standard":"std_proxy","version":"1.0.0","event":"set_ref","#,
+                r#""data":[{"old_ref":"std_basic.near","new_ref":"alice.near"}]}"#,
+            )],
+            take_emitted()
+        );
     }
 
     #[test]
-    fn test_set_ref() {
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_set_ref_fail() {
         let mut context = get_context();
         context.predecessor_account_id = bob();
 
         testing_env!(context);
         let mut contract = StdProxy::new(std_basic());
 
+        contract.owner = carol();
+
         assert_eq!(std_basic(), contract.get_ref());
 
         contract.set_ref(alice());
@@ -180,20 +297,118 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "NOT_AN_OWNER")]
-    fn test_set_ref_fail() {
+    fn test_implements_std_reference_proxy() {
+        fn assert_std_reference_proxy<T: StdReferenceProxy>() {}
+        assert_std_reference_proxy::<StdProxy>();
+    }
+
+    // Pausing itself is tested in `std_ref_interface::admin`.
+    #[test]
+    #[should_panic(expected = "PAUSED")]
+    fn test_get_reference_data_fail_because_paused() {
         let mut context = get_context();
         context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdProxy::new(std_basic());
+        contract.pause();
+
+        contract.get_reference_data_v2("BTC".into(), "USD".into());
+    }
 
+    #[test]
+    fn test_migrate_from_unversioned_state() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
         testing_env!(context);
+        env::state_write(&OldStdProxy { ref_: std_basic(), owner: bob() });
+
+        let contract = StdProxy::migrate();
+
+        assert_eq!(std_basic(), contract.ref_);
+        assert_eq!(bob(), contract.get_owner());
+        assert_eq!(None, contract.get_pending_owner());
+        assert_eq!(None, contract.get_guardian());
+        assert!(!contract.is_paused());
+        assert_eq!(STATE_VERSION, contract.get_state_version());
+        assert_eq!(
+            vec![concat!(
+                r#"EVENT_JSON:{"standard":"std_proxy","version":"1.0.0","event":"migrate","#,
+                r#""data":[{"from_version":0,"to_version":1}]}"#,
+            )],
+            take_emitted()
+        );
+    }
+
+    #[test]
+    fn test_migrate_current_state() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context.clone());
         let mut contract = StdProxy::new(std_basic());
+        contract.set_guardian(Some(carol()));
+        env::state_write(&contract);
 
-        contract.transfer_ownership(carol());
+        // the contract account itself may migrate right after deploying the new code
+        context.predecessor_account_id = alice();
+        context.storage_usage = env::storage_usage();
+        testing_env!(context);
+        let contract = StdProxy::migrate();
 
-        assert_eq!(std_basic(), contract.get_ref());
+        assert_eq!(Some(carol()), contract.get_guardian());
+        assert_eq!(STATE_VERSION, contract.get_state_version());
+    }
 
-        contract.set_ref(alice());
+    #[test]
+    #[should_panic(expected = "UNSUPPORTED_STATE_VERSION:2")]
+    fn test_migrate_fail_because_unsupported_version() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        env::state_write(&StdProxy::new(std_basic()));
+        upgrade::set_state_version(2);
 
-        assert_eq!(alice(), contract.get_ref());
+        StdProxy::migrate();
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_migrate_fail_because_not_owner() {
+        let mut context = get_context();
+        context.predecessor_account_id = carol();
+        testing_env!(context);
+        env::state_write(&OldStdProxy { ref_: std_basic(), owner: bob() });
+
+        StdProxy::migrate();
+    }
+
+    #[test]
+    fn test_upgrade() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let contract = StdProxy::new(std_basic());
+
+        contract.upgrade(Base64VecU8(b"code".to_vec()));
+        assert_eq!(
+            vec![concat!(
+                r#"EVENT_JSON:{"standard":"std_proxy","version":"1.0.0","event":"upgrade","#,
+                r#""data":[{"code_sha256":"#,
+                r#""5694d08a2e53ffcae0c3103e5ad6f6076abd960eb1f8a56577040bc1028f702b"}]}"#,
+            )],
+            take_emitted()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_upgrade_fail_because_not_owner() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context.clone());
+        let contract = StdProxy::new(std_basic());
+
+        context.predecessor_account_id = carol();
+        testing_env!(context);
+        contract.upgrade(Base64VecU8(b"code".to_vec()));
     }
 }
@@ -0,0 +1,309 @@
+#!/usr/bin/env python3
+"""Writes the proof fixtures used by the `relay_with_proof` tests.
+
+The proofs are signed by a test validator set whose private keys are derived from
+fixed seeds, so the output is reproducible. Everything is computed from scratch here
+rather than through the contract code, so that the tests cross-check the encoding.
+
+    python3 std_ref_basic/fixtures/generate_proofs.py
+"""
+
+import base64
+import hashlib
+import hmac
+import json
+import os
+import struct
+
+# secp256k1
+P = 2**256 - 2**32 - 977
+N = 0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141
+G = (
+    0x79BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798,
+    0x483ADA7726A3C4655DA4FBFC0E1108A8FD17B448A68554199C47D08FFB10D4B8,
+)
+
+
+def point_add(a, b):
+    if a is None:
+        return b
+    if b is None:
+        return a
+    if a[0] == b[0] and (a[1] + b[1]) % P == 0:
+        return None
+    if a == b:
+        slope = 3 * a[0] * a[0] * pow(2 * a[1], -1, P)
+    else:
+        slope = (b[1] - a[1]) * pow(b[0] - a[0], -1, P)
+    x = (slope * slope - a[0] - b[0]) % P
+    return (x, (slope * (a[0] - x) - a[1]) % P)
+
+
+def point_mul(k, point=G):
+    result = None
+    while k:
+        if k & 1:
+            result = point_add(result, point)
+        point = point_add(point, point)
+        k >>= 1
+    return result
+
+
+def compressed(point):
+    return bytes([2 + (point[1] & 1)]) + point[0].to_bytes(32, "big")
+
+
+def sign(private_key, digest):
+    """Returns (r, s, v) with a low s and v = 27 + recovery id."""
+    z = int.from_bytes(digest, "big")
+    k = int.from_bytes(hmac.new(private_key.to_bytes(32, "big"), digest, "sha256").digest(), "big")
+    k = k % (N - 1) + 1
+    point = point_mul(k)
+    r = point[0] % N
+    s = pow(k, -1, N) * (z + r * private_key) % N
+    recovery_id = (point[1] & 1) | (2 if point[0] >= N else 0)
+    if s > N // 2:
+        s = N - s
+        recovery_id ^= 1
+    return r.to_bytes(32, "big"), s.to_bytes(32, "big"), 27 + recovery_id
+
+
+# encodings
+def sha256(data):
+    return hashlib.sha256(data).digest()
+
+
+def varint(value):
+    encoded = b""
+    while value >= 0x80:
+        encoded += bytes([value & 0x7F | 0x80])
+        value >>= 7
+    return encoded + bytes([value])
+
+
+def leaf_hash(data):
+    return sha256(b"\x00" + data)
+
+
+def inner_hash(left, right):
+    return sha256(b"\x01" + left + right)
+
+
+def fake_hash(label):
+    return sha256(b"fixture:" + label.encode())
+
+
+def obi_string(value):
+    return struct.pack(">I", len(value)) + value.encode()
+
+
+def encode_result(result):
+    encoded = b""
+    for field, value in enumerate(
+        [
+            result["client_id"].encode(),
+            result["oracle_script_id"],
+            result["calldata"],
+            result["ask_count"],
+            result["min_count"],
+            result["request_id"],
+            result["ans_count"],
+            result["request_time"],
+            result["resolve_time"],
+            result["resolve_status"],
+            result["result"],
+        ],
+        start=1,
+    ):
+        if isinstance(value, bytes):
+            if value:
+                encoded += bytes([field << 3 | 2]) + varint(len(value)) + value
+        elif value:
+            encoded += bytes([field << 3]) + varint(value)
+    return encoded
+
+
+def b64(data):
+    return base64.b64encode(data).decode()
+
+
+CHAIN_ID = "band-fixtures"
+ORACLE_SCRIPT_ID = 3
+VALIDATORS = [int.from_bytes(sha256(b"validator-%d" % i), "big") % N for i in range(100)]
+POWER = 100
+
+
+def validator_set(count):
+    return [[b64(compressed(point_mul(key))), str(POWER)] for key in VALIDATORS[:count]]
+
+
+def proof(request_id, symbols, multiplier, rates, resolve_time, height, signers):
+    calldata = struct.pack(">I", len(symbols)) + b"".join(map(obi_string, symbols))
+    calldata += struct.pack(">Q", multiplier)
+    output = struct.pack(">I", len(rates)) + b"".join(struct.pack(">Q", r) for r in rates)
+    result = {
+        "client_id": "from_near",
+        "oracle_script_id": ORACLE_SCRIPT_ID,
+        "calldata": calldata,
+        "ask_count": 4,
+        "min_count": 3,
+        "request_id": request_id,
+        "ans_count": 4,
+        "request_time": resolve_time - 6,
+        "resolve_time": resolve_time,
+        "resolve_status": 1,
+        "result": output,
+    }
+
+    # the result leaf, up through three inner nodes of the IAVL tree
+    version = height - 1
+    node = sha256(
+        b"\x00\x02" + varint(2 * version) + b"\x09\xff" + struct.pack(">Q", request_id)
+        + b"\x20" + sha256(encode_result(result))
+    )
+    merkle_paths = []
+    for level, is_data_on_right in [(1, False), (2, True), (3, False)]:
+        sibling = fake_hash("iavl-%d-%d" % (request_id, level))
+        size, subtree_version = 2**level, version + level
+        left, right = (sibling, node) if is_data_on_right else (node, sibling)
+        node = sha256(
+            bytes([level << 1]) + varint(2 * size) + varint(2 * subtree_version)
+            + b"\x20" + left + b"\x20" + right
+        )
+        merkle_paths.append({
+            "is_data_on_right": is_data_on_right,
+            "subtree_height": level,
+            "subtree_size": str(size),
+            "subtree_version": str(subtree_version),
+            "sibling_hash": b64(sibling),
+        })
+    oracle_state_root = node
+
+    stores = {name: fake_hash("%s-%d" % (name, height)) for name in [
+        "acc_to_gov", "main_and_mint", "params", "slashing_to_upgrade",
+    ]}
+    app_hash = inner_hash(
+        inner_hash(
+            stores["acc_to_gov"],
+            inner_hash(
+                stores["main_and_mint"],
+                inner_hash(
+                    leaf_hash(b"\x06oracle\x20" + sha256(oracle_state_root)),
+                    stores["params"],
+                ),
+            ),
+        ),
+        stores["slashing_to_upgrade"],
+    )
+
+    header = {name: fake_hash("%s-%d" % (name, height)) for name in [
+        "version_and_chain_id", "last_block_id_and_other",
+        "next_validator_hash_and_consensus_hash", "last_results", "evidence_and_proposer",
+    ]}
+    time_second, time_nano_second = resolve_time + 2, 345678
+    block_hash = inner_hash(
+        inner_hash(
+            inner_hash(
+                header["version_and_chain_id"],
+                inner_hash(
+                    leaf_hash(b"\x08" + varint(height)),
+                    leaf_hash(b"\x08" + varint(time_second) + b"\x10" + varint(time_nano_second)),
+                ),
+            ),
+            inner_hash(
+                header["last_block_id_and_other"], header["next_validator_hash_and_consensus_hash"]
+            ),
+        ),
+        inner_hash(
+            inner_hash(leaf_hash(b"\x0a\x20" + app_hash), header["last_results"]),
+            header["evidence_and_proposer"],
+        ),
+    )
+
+    # a canonical precommit vote: type, height, block id hash and part set header,
+    # then the timestamp of each validator and the chain id
+    prefix = b"\x08\x02\x11" + struct.pack("<q", height) + b"\x22\x48\x0a\x20"
+    suffix = b"\x12\x24\x08\x01\x12\x20" + fake_hash("parts-%d" % height)
+    signatures = []
+    for index in signers:
+        key = VALIDATORS[index]
+        timestamp = b"\x08" + varint(time_second + 1) + b"\x10" + varint(1000 * (index + 1))
+        vote = prefix + block_hash + suffix + b"\x2a" + varint(len(timestamp)) + timestamp
+        vote += b"\x32" + varint(len(CHAIN_ID)) + CHAIN_ID.encode()
+        r, s, v = sign(key, sha256(varint(len(vote)) + vote))
+        signatures.append((compressed(point_mul(key)), {
+            "r": b64(r), "s": b64(s), "v": v, "encoded_timestamp": b64(timestamp),
+        }))
+    signatures.sort(key=lambda signature: signature[0])
+
+    return {
+        "block_relay_proof": {
+            "multi_store_proof": {
+                "acc_to_gov_stores_merkle_hash": b64(stores["acc_to_gov"]),
+                "main_and_mint_stores_merkle_hash": b64(stores["main_and_mint"]),
+                "oracle_iavl_state_hash": b64(oracle_state_root),
+                "params_stores_merkle_hash": b64(stores["params"]),
+                "slashing_to_upgrade_stores_merkle_hash": b64(stores["slashing_to_upgrade"]),
+            },
+            "block_header_merkle_parts": {
+                "version_and_chain_id_hash": b64(header["version_and_chain_id"]),
+                "height": str(height),
+                "time_second": str(time_second),
+                "time_nano_second": time_nano_second,
+                "last_block_id_and_other": b64(header["last_block_id_and_other"]),
+                "next_validator_hash_and_consensus_hash":
+                    b64(header["next_validator_hash_and_consensus_hash"]),
+                "last_results_hash": b64(header["last_results"]),
+                "evidence_and_proposer_hash": b64(header["evidence_and_proposer"]),
+            },
+            "common_encoded_vote_part": {
+                "signed_data_prefix": b64(prefix),
+                "signed_data_suffix": b64(suffix),
+            },
+            "signatures": [signature for _, signature in signatures],
+        },
+        "oracle_data_proof": {
+            "result": {
+                **{key: str(value) for key, value in result.items()},
+                "client_id": result["client_id"],
+                "calldata": b64(calldata),
+                "resolve_status": 1,
+                "result": b64(output),
+            },
+            "version": str(version),
+            "merkle_paths": merkle_paths,
+        },
+    }
+
+
+def main():
+    fixtures = {
+        "chain_id": CHAIN_ID,
+        "oracle_script_id": str(ORACLE_SCRIPT_ID),
+        "validators": validator_set(4),
+        # BTC at 20000.123456 and ETH at 1500, signed by three of the four validators
+        "btc_eth": proof(
+            1001, ["BTC", "ETH"], 1_000_000, [20_000_123_456, 1_500_000_000],
+            1_650_000_010, 5_001, [0, 1, 2],
+        ),
+        # a later result for BTC, signed by all four validators
+        "btc_later": proof(
+            1002, ["BTC"], 1_000_000, [20_100_000_000], 1_650_000_070, 5_012, [0, 1, 2, 3],
+        ),
+        # a validator set the size of BandChain's, with 90 of the 100 validators signing
+        "large_set": {
+            "validators": validator_set(100),
+            "proof": proof(
+                1003, ["BTC", "ETH"], 1_000_000, [20_200_000_000, 1_510_000_000],
+                1_650_000_130, 5_023, range(90),
+            ),
+        },
+    }
+    path = os.path.join(os.path.dirname(os.path.abspath(__file__)), "proofs.json")
+    with open(path, "w") as file:
+        json.dump(fixtures, file, indent=2)
+        file.write("\n")
+
+
+if __name__ == "__main__":
+    main()
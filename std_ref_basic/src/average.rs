@@ -0,0 +1,112 @@
+use crate::math::mul_div;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Per-symbol price accumulators, advanced on every relay.
+///
+/// `cumulative` is the sum of `rate * elapsed_ns` up to `updated_at`. It wraps on
+/// overflow, so only differences between two readings are meaningful, the same way
+/// as the price accumulators of Uniswap V2.
+///
+/// `ema` is a continuous-time moving average: over an interval of `dt` during which
+/// `rate` was in force it moves toward `rate` by a fraction `dt / (dt + period)`.
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, PartialEq)]
+pub struct Accumulator {
+    pub rate: u128,
+    pub cumulative: u128,
+    pub ema: u128,
+    pub updated_at: u64,
+}
+
+impl Accumulator {
+    pub fn new(rate: u128, now: u64) -> Self {
+        Self { rate, cumulative: 0, ema: rate, updated_at: now }
+    }
+
+    /// Returns the accumulators as of `now`, assuming `rate` stays in force.
+    pub fn advance(&self, now: u64, ema_period: u64) -> Self {
+        let dt = now.saturating_sub(self.updated_at);
+        Self {
+            rate: self.rate,
+            cumulative: self.cumulative.wrapping_add(self.rate.wrapping_mul(dt as u128)),
+            ema: ema_step(self.ema, self.rate, dt, ema_period),
+            updated_at: now.max(self.updated_at),
+        }
+    }
+
+    /// Advances to `now` and switches to the newly relayed `rate`.
+    pub fn update(&mut self, rate: u128, now: u64, ema_period: u64) {
+        *self = self.advance(now, ema_period);
+        self.rate = rate;
+    }
+}
+
+/// Average rate between two readings `(cumulative, timestamp)` of an accumulator.
+pub fn twap(start: (u128, u64), end: (u128, u64)) -> Option<u128> {
+    let elapsed = end.1.checked_sub(start.1).filter(|&elapsed| elapsed > 0)?;
+    Some(end.0.wrapping_sub(start.0) / elapsed as u128)
+}
+
+fn ema_step(ema: u128, rate: u128, dt: u64, period: u64) -> u128 {
+    if dt == 0 {
+        return ema;
+    }
+    let total = dt as u128 + period as u128;
+    // the moved distance never exceeds `|rate - ema|`, so neither branch overflows
+    if rate >= ema {
+        ema + mul_div(rate - ema, dt as u128, total).unwrap()
+    } else {
+        ema - mul_div(ema - rate, dt as u128, total).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_advance_accumulates_time_weighted_rate() {
+        let mut acc = Accumulator::new(100, 1_000);
+        acc.update(300, 1_010, 0);
+        assert_eq!(1_000, acc.cumulative);
+        assert_eq!(300, acc.rate);
+
+        let later = acc.advance(1_040, 0);
+        assert_eq!(10_000, later.cumulative);
+        assert_eq!(Some(250), twap((0, 1_000), (later.cumulative, later.updated_at)));
+        assert_eq!(Some(300), twap((acc.cumulative, 1_010), (later.cumulative, 1_040)));
+        assert_eq!(None, twap((later.cumulative, 1_040), (later.cumulative, 1_040)));
+    }
+
+    #[test]
+    fn test_twap_across_wrapping() {
+        let acc = Accumulator { rate: 10, cumulative: u128::MAX - 4, ema: 10, updated_at: 0 };
+        let later = acc.advance(2, 0);
+        assert_eq!(15, later.cumulative);
+        assert_eq!(Some(10), twap((acc.cumulative, 0), (later.cumulative, 2)));
+    }
+
+    #[test]
+    fn test_ema() {
+        let mut acc = Accumulator::new(100, 0);
+        acc.update(200, 10, 10);
+        // the first interval was all at the initial rate
+        assert_eq!(100, acc.ema);
+        // 200 held for one period moves the average half way
+        assert_eq!(150, acc.advance(20, 10).ema);
+        // and toward it from above as well
+        acc.update(0, 20, 10);
+        assert_eq!(150, acc.ema);
+        assert_eq!(75, acc.advance(30, 10).ema);
+        // a zero period follows the rate immediately
+        assert_eq!(0, acc.advance(21, 0).ema);
+        // advancing to the same time is a no-op
+        assert_eq!(acc, acc.advance(20, 10));
+    }
+
+    #[test]
+    fn test_ema_with_large_values() {
+        let acc = Accumulator { rate: u128::MAX, cumulative: 0, ema: 0, updated_at: 0 };
+        assert_eq!(u128::MAX / 2, acc.advance(u64::MAX, u64::MAX).ema);
+        assert_eq!(u128::MAX, acc.advance(u64::MAX, 0).ema);
+    }
+}
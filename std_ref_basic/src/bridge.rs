@@ -0,0 +1,104 @@
+use crate::proof::{Proof, RESOLVE_STATUS_SUCCESS};
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::collections::UnorderedMap;
+use near_sdk::json_types::U64;
+use serde::{Deserialize, Serialize};
+use std_reference_interface::errors::Error;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BridgeConfig {
+    /// Chain id of BandChain, signed as part of every precommit vote.
+    pub chain_id: String,
+    /// The oracle script whose results may be relayed.
+    pub oracle_script_id: U64,
+}
+
+/// The BandChain validator set, against which proofs of oracle results are verified.
+///
+/// Validators are identified by their compressed secp256k1 public key. A proof is
+/// accepted when the validators that signed its block hold more than two thirds of
+/// the total voting power. Signers outside of the set are ignored.
+#[derive(Default, BorshDeserialize, BorshSerialize)]
+pub struct Bridge {
+    pub config: Option<BridgeConfig>,
+    total_power: u64,
+    validators: UnorderedMap<Vec<u8>, u64>,
+}
+
+impl Bridge {
+    pub fn new(prefix: &[u8]) -> Self {
+        Self {
+            config: None,
+            total_power: 0,
+            validators: UnorderedMap::new([prefix, b"v"].concat()),
+        }
+    }
+
+    pub fn total_power(&self) -> u64 {
+        self.total_power
+    }
+
+    pub fn validators(&self) -> Vec<(Vec<u8>, u64)> {
+        self.validators.to_vec()
+    }
+
+    /// Sets the voting power of `pub_key`, removing it from the set for a power of 0.
+    /// Only the encoding of the key is checked: a key that is not on the curve can never
+    /// sign and so only adds power that is never reached.
+    pub fn set_power(&mut self, pub_key: &[u8], power: u64) -> Result<(), Error> {
+        if pub_key.len() != 33 || (pub_key[0] != 2 && pub_key[0] != 3) {
+            return Err(Error::InvalidValidatorPubKey);
+        }
+        let previous = self.validators.get(&pub_key.to_vec()).unwrap_or(0);
+        self.total_power = self
+            .total_power
+            .checked_sub(previous)
+            .and_then(|total| total.checked_add(power))
+            .ok_or(Error::ValidatorPowerOverflow)?;
+        match power {
+            0 => self.validators.remove(&pub_key.to_vec()),
+            _ => self.validators.insert(&pub_key.to_vec(), &power),
+        };
+        Ok(())
+    }
+
+    /// Verifies `proof` and returns the height of the block it was proven in.
+    pub fn verify(&self, proof: &Proof) -> Result<u64, Error> {
+        let config = self.config.as_ref().ok_or(Error::BridgeNotConfigured)?;
+        let oracle_data = &proof.oracle_data_proof;
+        let result = &oracle_data.result;
+        if result.oracle_script_id != config.oracle_script_id {
+            let oracle_script_id = result.oracle_script_id;
+            return Err(Error::UnexpectedOracleScript { oracle_script_id });
+        }
+        if result.resolve_status != RESOLVE_STATUS_SUCCESS {
+            return Err(Error::UnsuccessfulRequest { request_id: result.request_id });
+        }
+
+        let block = &proof.block_relay_proof;
+        let oracle_state_root = oracle_data.oracle_state_root()?;
+        if oracle_state_root[..] != block.multi_store_proof.oracle_iavl_state_hash.0[..] {
+            return Err(Error::OracleStateRootMismatch);
+        }
+        let app_hash = block.multi_store_proof.app_hash()?;
+        let block_hash = block.block_header_merkle_parts.block_hash(&app_hash)?;
+        let common = &block.common_encoded_vote_part;
+        common.check(block.block_header_merkle_parts.height.0)?;
+
+        let mut signed_power = 0u128;
+        let mut last_signer: Option<[u8; 33]> = None;
+        for signature in &block.signatures {
+            let signer = signature.recover_signer(&block_hash, common, &config.chain_id)?;
+            // Sorting the signers rules out counting a validator twice.
+            if last_signer.is_some_and(|last| last >= signer) {
+                return Err(Error::UnsortedSigners);
+            }
+            last_signer = Some(signer);
+            signed_power += self.validators.get(&signer.to_vec()).unwrap_or(0) as u128;
+        }
+        if signed_power * 3 <= self.total_power as u128 * 2 {
+            return Err(Error::NotEnoughVotingPower);
+        }
+        Ok(block.block_header_merkle_parts.height.0)
+    }
+}
@@ -0,0 +1,195 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::collections::UnorderedMap;
+use near_sdk::json_types::{U128, U64};
+use serde::{Deserialize, Serialize};
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub rate: U128,
+    pub resolve_time: U64,
+    pub request_id: U64,
+    /// Block timestamp of the relay that wrote this entry.
+    pub relayed_at: U64,
+    /// Value of the TWAP accumulator at `relayed_at`, before `rate` came into force.
+    pub cumulative: U128,
+}
+
+/// Bounded per-symbol history of relayed references.
+///
+/// Entries of a symbol are numbered by an increasing sequence number and only the
+/// `[first, next)` range is kept. Pushing beyond `depth` entries removes the oldest
+/// ones from storage. Lowering `depth` takes effect on the next push of each symbol.
+#[derive(Default, BorshDeserialize, BorshSerialize)]
+pub struct History {
+    pub depth: u64,
+    entries: UnorderedMap<(String, u64), HistoryEntry>,
+    bounds: UnorderedMap<String, (u64, u64)>,
+}
+
+impl History {
+    pub fn new(prefix: &[u8], depth: u64) -> Self {
+        Self {
+            depth,
+            entries: UnorderedMap::new([prefix, b"e"].concat()),
+            bounds: UnorderedMap::new([prefix, b"b"].concat()),
+        }
+    }
+
+    pub fn len(&self, symbol: &str) -> u64 {
+        let (first, next) = self.bounds(symbol);
+        next - first
+    }
+
+    pub fn push(&mut self, symbol: &str, entry: &HistoryEntry) {
+        let (mut first, next) = self.bounds(symbol);
+        self.entries.insert(&(symbol.to_string(), next), entry);
+        let next = next + 1;
+        while next - first > self.depth {
+            self.entries.remove(&(symbol.to_string(), first));
+            first += 1;
+        }
+        if first == next {
+            self.bounds.remove(&symbol.to_string());
+        } else {
+            self.bounds.insert(&symbol.to_string(), &(first, next));
+        }
+    }
+
+    /// Returns up to `limit` entries starting at the `from`-th oldest one.
+    pub fn range(&self, symbol: &str, from: u64, limit: u64) -> Vec<HistoryEntry> {
+        let (first, next) = self.bounds(symbol);
+        let start = first.saturating_add(from).min(next);
+        let end = start.saturating_add(limit).min(next);
+        (start..end).filter_map(|seq| self.entries.get(&(symbol.to_string(), seq))).collect()
+    }
+
+    /// Returns the latest entry relayed at or before `timestamp`.
+    pub fn at(&self, symbol: &str, timestamp: u64) -> Option<HistoryEntry> {
+        let (first, next) = self.bounds(symbol);
+        let entry = |seq: u64| self.entries.get(&(symbol.to_string(), seq)).unwrap();
+        if first == next || entry(first).relayed_at.0 > timestamp {
+            return None;
+        }
+        // `relayed_at` never decreases, so search for the last entry not after `timestamp`.
+        let (mut low, mut high) = (first, next - 1);
+        while low < high {
+            let mid = low + (high - low).div_ceil(2);
+            if entry(mid).relayed_at.0 <= timestamp {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        Some(entry(low))
+    }
+
+    pub fn clear(&mut self, symbol: &str) {
+        let (first, next) = self.bounds(symbol);
+        for seq in first..next {
+            self.entries.remove(&(symbol.to_string(), seq));
+        }
+        self.bounds.remove(&symbol.to_string());
+    }
+
+    fn bounds(&self, symbol: &str) -> (u64, u64) {
+        self.bounds.get(&symbol.to_string()).unwrap_or((0, 0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::get_context;
+    use near_sdk::testing_env;
+    use near_sdk::MockedBlockchain;
+
+    fn entry(rate: u128, relayed_at: u64) -> HistoryEntry {
+        HistoryEntry {
+            rate: U128(rate),
+            resolve_time: U64(relayed_at),
+            request_id: U64(0),
+            relayed_at: U64(relayed_at),
+            cumulative: U128(0),
+        }
+    }
+
+    #[test]
+    fn test_push_and_range() {
+        testing_env!(get_context());
+        let mut history = History::new(b"h", 3);
+        let btc = "BTC";
+
+        for i in 1..=5 {
+            history.push(btc, &entry(i, i as u64 * 10));
+        }
+
+        assert_eq!(3, history.len(btc));
+        assert_eq!(vec![entry(3, 30), entry(4, 40), entry(5, 50)], history.range(btc, 0, 10));
+        assert_eq!(vec![entry(4, 40)], history.range(btc, 1, 1));
+        assert_eq!(Vec::<HistoryEntry>::new(), history.range(btc, 3, 10));
+        assert_eq!(Vec::<HistoryEntry>::new(), history.range("ETH", 0, 10));
+
+        // entries that fell off the ring are removed from storage
+        assert_eq!(3, history.entries.len());
+    }
+
+    #[test]
+    fn test_lower_depth() {
+        testing_env!(get_context());
+        let mut history = History::new(b"h", 3);
+        let btc = "BTC";
+
+        for i in 1..=3 {
+            history.push(btc, &entry(i, i as u64 * 10));
+        }
+        history.depth = 1;
+        history.push(btc, &entry(4, 40));
+
+        assert_eq!(vec![entry(4, 40)], history.range(btc, 0, 10));
+        assert_eq!(1, history.entries.len());
+
+        history.depth = 0;
+        history.push(btc, &entry(5, 50));
+
+        assert_eq!(0, history.len(btc));
+        assert_eq!(0, history.entries.len());
+        assert_eq!(0, history.bounds.len());
+    }
+
+    #[test]
+    fn test_clear() {
+        testing_env!(get_context());
+        let mut history = History::new(b"h", 3);
+
+        for i in 1..=2 {
+            history.push("BTC", &entry(i, i as u64 * 10));
+            history.push("ETH", &entry(i, i as u64 * 10));
+        }
+        history.clear("BTC");
+        history.clear("DOGE");
+
+        assert_eq!(0, history.len("BTC"));
+        assert_eq!(vec![entry(1, 10), entry(2, 20)], history.range("ETH", 0, 10));
+        assert_eq!(2, history.entries.len());
+        assert_eq!(1, history.bounds.len());
+    }
+
+    #[test]
+    fn test_at() {
+        testing_env!(get_context());
+        let mut history = History::new(b"h", 10);
+        let btc = "BTC";
+
+        assert_eq!(None, history.at(btc, 100));
+        for i in 1..=5 {
+            history.push(btc, &entry(i, i as u64 * 10));
+        }
+
+        assert_eq!(None, history.at(btc, 9));
+        assert_eq!(Some(entry(1, 10)), history.at(btc, 10));
+        assert_eq!(Some(entry(1, 10)), history.at(btc, 19));
+        assert_eq!(Some(entry(3, 30)), history.at(btc, 30));
+        assert_eq!(Some(entry(4, 40)), history.at(btc, 49));
+        assert_eq!(Some(entry(5, 50)), history.at(btc, 1000));
+    }
+}
@@ -1,11 +1,44 @@
 use borsh::{BorshDeserialize, BorshSerialize};
-use near_sdk::collections::UnorderedMap;
-use near_sdk::{env, near_bindgen, AccountId};
+use near_sdk::collections::{UnorderedMap, UnorderedSet};
+use near_sdk::json_types::{Base64VecU8, U128, U64};
+use near_sdk::{env, near_bindgen, AccountId, Promise};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std_reference_interface::events::EventStandard;
+use std_reference_interface::pagination::paginate;
+use std_reference_interface::{admin, upgrade};
+pub use std_reference_interface::{errors::Error, RefData, ReferenceData, StdReference};
+
+mod average;
+mod bridge;
+mod history;
+mod math;
+mod order;
+pub mod proof;
+mod rounds;
+#[cfg(test)]
+mod test_utils;
+
+use average::Accumulator;
+use bridge::Bridge;
+pub use bridge::BridgeConfig;
+pub use history::HistoryEntry;
+use history::History;
+use order::RelayOrder;
+use rounds::Rounds;
+use proof::Proof;
+pub use rounds::Submission;
 
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
 static E9: u128 = 1_000_000_000;
+const DEFAULT_HISTORY_DEPTH: u64 = 10;
+const DEFAULT_EMA_PERIOD: u64 = 3_600_000_000_000;
+const DEFAULT_QUORUM: u64 = 1;
+const DEFAULT_ROUND_TIMEOUT: u64 = 600_000_000_000;
+const EVENTS: EventStandard = EventStandard { standard: "std_reference_basic", version: "1.0.0" };
+pub const STATE_VERSION: u32 = 3;
 
 macro_rules! zip {
     ($x: expr) => ($x);
@@ -14,373 +47,3785 @@ macro_rules! zip {
     )
 }
 
+/// Registration of a relayable symbol. Relayed rates are scaled by `multiplier` and
+/// stored normalized to `E9`. A deprecated symbol keeps its last reference readable
+/// but can no longer be relayed.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SymbolInfo {
+    pub multiplier: U128,
+    pub description: String,
+    pub deprecated: bool,
+}
+
+/// A relayed rate held back by the circuit breaker, see `set_max_deviation`.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PendingUpdate {
+    pub rate: U128,
+    pub resolve_time: U64,
+    pub request_id: U64,
+    /// The stored rate the update deviated from.
+    pub previous_rate: U128,
+    /// Block timestamp of the relay that tripped the breaker.
+    pub relayed_at: U64,
+    /// Who relayed it, who cannot corroborate it.
+    pub relayer: AccountId,
+}
+
 #[near_bindgen]
 #[derive(Default, BorshDeserialize, BorshSerialize)]
 pub struct StdReferenceBasic {
     pub refs: UnorderedMap<String, (u128, u64, u64)>,
     pub owner: AccountId,
+    pub relayers: UnorderedSet<AccountId>,
+    pub default_max_age: u64,
+    pub max_ages: UnorderedMap<String, u64>,
+    pub history: History,
+    pub accumulators: UnorderedMap<String, Accumulator>,
+    pub ema_period: u64,
+    pub anchors: UnorderedMap<String, u128>,
+    pub symbols: UnorderedMap<String, SymbolInfo>,
+    pub pending_owner: Option<AccountId>,
+    pub feeders: UnorderedSet<AccountId>,
+    pub rounds: Rounds,
+    pub guardian: Option<AccountId>,
+    pub max_deviations: UnorderedMap<String, u64>,
+    pub pending_updates: UnorderedMap<String, PendingUpdate>,
+    pub paused: bool,
+    pub bridge: Bridge,
+    pub relay_order: RelayOrder,
+}
+
+// The unversioned state layout, in which the owner was the only relayer.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct OldStdReferenceBasic {
+    pub refs: UnorderedMap<String, (u128, u64, u64)>,
+    pub owner: AccountId,
+}
+
+// The state layout of version 1, before proofs of BandChain results could be relayed.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct StdReferenceBasicV1 {
+    pub refs: UnorderedMap<String, (u128, u64, u64)>,
+    pub owner: AccountId,
+    pub relayers: UnorderedSet<AccountId>,
+    pub default_max_age: u64,
+    pub max_ages: UnorderedMap<String, u64>,
+    pub history: History,
+    pub accumulators: UnorderedMap<String, Accumulator>,
+    pub ema_period: u64,
+    pub anchors: UnorderedMap<String, u128>,
+    pub symbols: UnorderedMap<String, SymbolInfo>,
+    pub pending_owner: Option<AccountId>,
+    pub feeders: UnorderedSet<AccountId>,
+    pub rounds: Rounds,
+    pub guardian: Option<AccountId>,
+    pub max_deviations: UnorderedMap<String, u64>,
+    pub pending_updates: UnorderedMap<String, PendingUpdate>,
+    pub paused: bool,
+}
+
+// The state layout of version 2, in which `list_refs` followed the order of `refs`.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct StdReferenceBasicV2 {
+    pub refs: UnorderedMap<String, (u128, u64, u64)>,
+    pub owner: AccountId,
+    pub relayers: UnorderedSet<AccountId>,
+    pub default_max_age: u64,
+    pub max_ages: UnorderedMap<String, u64>,
+    pub history: History,
+    pub accumulators: UnorderedMap<String, Accumulator>,
+    pub ema_period: u64,
+    pub anchors: UnorderedMap<String, u128>,
+    pub symbols: UnorderedMap<String, SymbolInfo>,
+    pub pending_owner: Option<AccountId>,
+    pub feeders: UnorderedSet<AccountId>,
+    pub rounds: Rounds,
+    pub guardian: Option<AccountId>,
+    pub max_deviations: UnorderedMap<String, u64>,
+    pub pending_updates: UnorderedMap<String, PendingUpdate>,
+    pub paused: bool,
+    pub bridge: Bridge,
 }
 
 #[near_bindgen]
 impl StdReferenceBasic {
     #[init]
     pub fn new() -> Self {
-        assert!(!env::state_exists(), "ALREADY_INITIALIZED");
-        Self {
-            refs: UnorderedMap::new(b"refs".to_vec()),
-            owner: env::signer_account_id(),
-        }
+        assert!(!env::state_exists(), "{}", Error::AlreadyInitialized);
+        upgrade::set_state_version(STATE_VERSION);
+        Self::with_owner(env::signer_account_id())
     }
 
-    pub fn get_owner(&self) -> AccountId {
-        self.owner.clone()
+    /// See `std_reference_interface::upgrade`. Relayed symbols get registered and ordered.
+    #[init]
+    pub fn migrate() -> Self {
+        let convert = |version| match version {
+            0 => Some(Self::from_unversioned(upgrade::read_state())),
+            1 => Some(Self::from_v1(upgrade::read_state())),
+            2 => Some(Self::from_v2(upgrade::read_state())),
+            STATE_VERSION => Some(upgrade::read_state()),
+            _ => None,
+        };
+        upgrade::migrate(&EVENTS, STATE_VERSION, convert, |contract: &Self| &contract.owner)
     }
 
-    pub fn transfer_ownership(&mut self, new_owner: AccountId) {
-        assert!(env::predecessor_account_id() == self.get_owner(), "NOT_AN_OWNER");
-        env::log(format!("transfer ownership from {} to {}", self.owner, new_owner).as_bytes());
-        self.owner = new_owner;
+    pub fn get_state_version(&self) -> u32 {
+        upgrade::state_version()
     }
 
-    pub fn get_refs(&self, symbol: String) -> Option<(u128, u64, u64)> {
-        match &symbol[..] {
-            "USD" => Some((E9, env::block_timestamp(), 0)),
-            _ => self.refs.get(&symbol),
-        }
+    /// See `std_reference_interface::upgrade`.
+    pub fn upgrade(&self, code: Base64VecU8) -> Promise {
+        upgrade::upgrade(&EVENTS, &self.owner, code.0)
     }
 
-    pub fn get_reference_data(&self, base: String, quote: String) -> Option<(u128, u64, u64)> {
-        match (self.get_refs(base.clone()), self.get_refs(quote.clone())) {
-            (Some((br, bt, _)), Some((qr, qt, _))) => return Some((br * E9 * E9 / qr, bt, qt)),
-            (None, Some(_)) => env::log(format!("REF_DATA_NOT_AVAILABLE_FOR: {}", base).as_bytes()),
-            (Some(_), None) => {
-                env::log(format!("REF_DATA_NOT_AVAILABLE_FOR: {}", quote).as_bytes())
-            }
-            _ => env::log(format!("REF_DATA_NOT_AVAILABLE_FOR: {} and {}", base, quote).as_bytes()),
-        }
-        None
+    pub fn get_bridge_config(&self) -> Option<BridgeConfig> {
+        self.bridge.config.clone()
     }
 
-    pub fn get_reference_data_bulk(
-        &self,
-        bases: Vec<String>,
-        quotes: Vec<String>,
-    ) -> Option<Vec<(u128, u64, u64)>> {
-        assert!(bases.len() == quotes.len(), "BAD_INPUT_LENGTH");
-        bases
-            .iter()
-            .zip(quotes.iter())
-            .map(|(b, q)| self.get_reference_data(b.clone(), q.clone()))
-            .collect()
+    pub fn set_bridge_config(&mut self, config: BridgeConfig) {
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        EVENTS.emit("set_bridge_config", json!(config));
+        self.bridge.config = Some(config);
     }
 
-    pub fn relay(
-        &mut self,
-        symbols: Vec<String>,
-        rates: Vec<String>,
-        resolve_times: Vec<u64>,
-        request_ids: Vec<u64>,
-    ) {
-        assert!(env::predecessor_account_id() == self.get_owner(), "NOT_AN_OWNER");
+    /// Lists the BandChain validators by compressed secp256k1 public key.
+    pub fn get_validators(&self) -> Vec<(Base64VecU8, U64)> {
+        let validators = self.bridge.validators().into_iter();
+        validators.map(|(pub_key, power)| (pub_key.into(), power.into())).collect()
+    }
 
-        let len = symbols.len();
-        assert!(rates.len() == len, "BAD_RATES_LENGTH");
-        assert!(resolve_times.len() == len, "BAD_RESOLVE_TIMES_LENGTH");
-        assert!(request_ids.len() == len, "BAD_REQUEST_IDS_LENGTH");
+    pub fn get_total_validator_power(&self) -> U64 {
+        self.bridge.total_power().into()
+    }
 
-        for (s, (r, (rt, rid))) in zip!(&symbols, &rates, &resolve_times, &request_ids) {
-            let rate_opt = r.parse::<u128>().ok();
-            assert!(rate_opt != None, format!("FAIL_TO_PARSE_RATE_{}_FOR_{}", r, s));
-            self.refs.insert(&s, &(rate_opt.unwrap(), rt, rid));
-            env::log(format!("relay: {},{},{},{}", s, r, rt, rid).as_bytes());
+    /// Sets the voting power of each given validator. A power of 0 removes it.
+    pub fn update_validator_powers(&mut self, validators: Vec<(Base64VecU8, U64)>) {
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        let mut updated = vec![];
+        for (pub_key, power) in validators {
+            self.bridge.set_power(&pub_key.0, power.0).unwrap_or_else(|e| panic!("{}", e));
+            updated.push(json!({"pub_key": pub_key, "power": power}));
         }
+        EVENTS.emit("update_validator_powers", updated.into());
     }
-}
 
-// use the attribute below for unit tests
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use near_sdk::MockedBlockchain;
-    use near_sdk::{testing_env, VMContext};
+    pub fn get_owner(&self) -> AccountId {
+        self.owner.clone()
+    }
 
-    fn alice() -> AccountId {
-        "alice.near".to_string()
+    pub fn get_pending_owner(&self) -> Option<AccountId> {
+        self.pending_owner.clone()
     }
 
-    fn bob() -> AccountId {
-        "bob.near".to_string()
+    /// See `std_reference_interface::admin::propose_ownership`.
+    pub fn propose_ownership(&mut self, new_owner: AccountId) {
+        admin::propose_ownership(&EVENTS, &self.owner, &mut self.pending_owner, new_owner);
     }
 
-    fn carol() -> AccountId {
-        "carol.near".to_string()
+    pub fn accept_ownership(&mut self) {
+        admin::accept_ownership(&EVENTS, &mut self.owner, &mut self.pending_owner);
     }
 
-    fn get_context() -> VMContext {
-        VMContext {
-            current_account_id: alice(),
-            signer_account_id: bob(),
-            signer_account_pk: vec![0, 1, 2],
-            predecessor_account_id: carol(),
-            input: vec![],
-            block_index: 0,
-            block_timestamp: 0,
-            account_balance: 0,
-            account_locked_balance: 0,
-            storage_usage: 0,
-            attached_deposit: 0,
-            prepaid_gas: 10u64.pow(18),
-            random_seed: vec![0, 1, 2],
-            is_view: false,
-            output_data_receivers: vec![],
-            epoch_height: 0,
-        }
+    pub fn cancel_ownership_proposal(&mut self) {
+        admin::cancel_ownership_proposal(&EVENTS, &self.owner, &mut self.pending_owner);
     }
 
-    #[test]
-    fn test_create_new_contract() {
-        let context = get_context();
-        testing_env!(context);
-        let contract = StdReferenceBasic::new();
+    pub fn is_relayer(&self, account: AccountId) -> bool {
+        self.relayers.contains(&account)
+    }
 
-        // check state
-        assert_eq!(bob(), contract.owner);
-        assert_eq!(true, contract.refs.is_empty());
+    pub fn get_relayers(&self) -> Vec<AccountId> {
+        self.relayers.to_vec()
+    }
 
-        // check owner using view function
-        assert_eq!(bob(), contract.get_owner());
+    pub fn add_relayer(&mut self, relayer: AccountId) {
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        assert!(self.relayers.insert(&relayer), "{}", Error::AlreadyARelayer);
+        EVENTS.emit("add_relayer", json!({ "relayer": relayer }));
     }
 
-    #[test]
-    fn test_transfer_ownership() {
-        let mut context = get_context();
-        context.predecessor_account_id = bob();
+    pub fn remove_relayer(&mut self, relayer: AccountId) {
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        assert!(self.relayers.remove(&relayer), "{}", Error::NotARelayer);
+        EVENTS.emit("remove_relayer", json!({ "relayer": relayer }));
+    }
 
-        testing_env!(context);
-        let mut contract = StdReferenceBasic::new();
+    pub fn is_feeder(&self, account: AccountId) -> bool {
+        self.feeders.contains(&account)
+    }
 
-        // check owner using view function
-        assert_eq!(bob(), contract.get_owner());
-        // transfer the ownership bob -> alice
-        contract.transfer_ownership(alice());
-        // check owner using view function
-        assert_eq!(alice(), contract.get_owner());
+    pub fn get_feeders(&self) -> Vec<AccountId> {
+        self.feeders.to_vec()
     }
 
-    #[test]
-    #[should_panic(expected = "NOT_AN_OWNER")]
-    fn test_transfer_ownership_fail() {
-        let mut context = get_context();
-        context.predecessor_account_id = bob();
+    pub fn add_feeder(&mut self, feeder: AccountId) {
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        assert!(self.feeders.insert(&feeder), "{}", Error::AlreadyAFeeder);
+        EVENTS.emit("add_feeder", json!({ "feeder": feeder }));
+    }
 
-        testing_env!(context);
-        let mut contract = StdReferenceBasic::new();
+    pub fn remove_feeder(&mut self, feeder: AccountId) {
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        assert!(self.feeders.remove(&feeder), "{}", Error::NotAFeeder);
+        EVENTS.emit("remove_feeder", json!({ "feeder": feeder }));
+    }
 
-        // check owner using view function
-        assert_eq!(bob(), contract.get_owner());
-        // transfer the ownership bob -> alice
-        contract.transfer_ownership(alice());
-        // check owner using view function
-        assert_eq!(alice(), contract.get_owner());
+    pub fn get_quorum(&self) -> u64 {
+        self.rounds.quorum
+    }
 
-        // transfer the ownership alice -> bob
-        // should fail because bob is not an owner anymore
-        contract.transfer_ownership(alice());
+    /// Sets how many feeder submissions finalize a round. Open rounds that already hold
+    /// enough submissions are finalized by their next submission.
+    pub fn set_quorum(&mut self, quorum: u64) {
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        assert!(quorum > 0, "{}", Error::ZeroQuorum);
+        EVENTS.emit(
+            "set_quorum",
+            json!({"old_quorum": U64(self.rounds.quorum), "new_quorum": U64(quorum)}),
+        );
+        self.rounds.quorum = quorum;
     }
 
-    #[test]
-    fn test_get_refs_usd() {
-        let context = get_context();
-        testing_env!(context.clone());
-        let contract = StdReferenceBasic::new();
+    pub fn get_round_timeout(&self) -> u64 {
+        self.rounds.timeout
+    }
 
-        assert_eq!(
-            Some((E9, context.block_timestamp, 0)),
-            contract.get_refs("USD".into())
+    /// Sets how many nanoseconds a submission counts toward the quorum of its round.
+    pub fn set_round_timeout(&mut self, timeout: u64) {
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        EVENTS.emit(
+            "set_round_timeout",
+            json!({"old_timeout": U64(self.rounds.timeout), "new_timeout": U64(timeout)}),
         );
+        self.rounds.timeout = timeout;
     }
 
-    #[test]
-    fn test_get_refs_symbol_not_found() {
-        let context = get_context();
-        testing_env!(context.clone());
-        let contract = StdReferenceBasic::new();
+    pub fn get_finalized_round(&self, symbol: String) -> Option<u64> {
+        self.rounds.finalized(&symbol)
+    }
 
-        assert_eq!(None, contract.get_refs("BTC".into()));
+    /// Submissions of `round_id`, which are only kept for the open round of `symbol` and
+    /// the last finalized one. Those of the open round may include submissions that
+    /// expired or whose feeder was removed, which no longer count toward the quorum.
+    pub fn get_round_submissions(&self, symbol: String, round_id: u64) -> Vec<Submission> {
+        self.rounds.submissions(&symbol, round_id)
     }
 
-    #[test]
-    fn test_get_reference_data_usd() {
-        let context = get_context();
-        testing_env!(context.clone());
-        let contract = StdReferenceBasic::new();
+    pub fn get_guardian(&self) -> Option<AccountId> {
+        self.guardian.clone()
+    }
 
-        assert_eq!(
-            Some((E9 * E9, context.block_timestamp, context.block_timestamp)),
-            contract.get_reference_data("USD".into(), "USD".into())
-        );
+    /// Sets the account that may pause the contract and confirm or reject pending
+    /// updates besides the owner.
+    pub fn set_guardian(&mut self, guardian: Option<AccountId>) {
+        admin::set_guardian(&EVENTS, &self.owner, &mut self.guardian, guardian);
     }
 
-    #[test]
-    fn test_get_reference_data_not_found() {
-        let context = get_context();
-        testing_env!(context.clone());
-        let contract = StdReferenceBasic::new();
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
 
-        assert_eq!(
-            None,
-            contract.get_reference_data("BTC".into(), "USD".into())
-        );
+    /// Halts `relay`, `submit` and `confirm_pending_update`, and makes the rate views,
+    /// `list_refs` and `get_history` fail with `PAUSED`. The owner can still
+    /// `force_relay` corrections.
+    pub fn pause(&mut self) {
+        admin::pause(&EVENTS, &self.owner, &self.guardian, &mut self.paused);
     }
 
-    #[test]
-    fn test_get_reference_data_bulk_usd() {
-        let context = get_context();
-        testing_env!(context.clone());
-        let contract = StdReferenceBasic::new();
+    pub fn unpause(&mut self) {
+        admin::unpause(&EVENTS, &self.owner, &mut self.paused);
+    }
 
-        assert_eq!(
-            Some(vec![
-                (E9 * E9, context.block_timestamp, context.block_timestamp),
-                (E9 * E9, context.block_timestamp, context.block_timestamp)
-            ]),
-            contract.get_reference_data_bulk(
-                vec!["USD".into(), "USD".into()],
-                vec!["USD".into(), "USD".into()]
-            )
-        );
+    pub fn get_anchors(&self) -> Vec<(String, U128)> {
+        self.anchors.iter().map(|(symbol, rate)| (symbol, rate.into())).collect()
     }
 
-    #[test]
-    fn test_get_reference_data_bulk_not_found() {
-        let context = get_context();
-        testing_env!(context.clone());
-        let contract = StdReferenceBasic::new();
+    pub fn set_anchor(&mut self, symbol: String, rate: U128) {
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        assert!(rate.0 > 0, "{}", Error::ZeroAnchorRate { symbol });
+        EVENTS.emit("set_anchor", json!({"symbol": symbol, "rate": rate}));
+        self.anchors.insert(&symbol, &rate.0);
+    }
 
-        assert_eq!(
-            None,
-            contract.get_reference_data_bulk(
-                vec!["BTC".into(), "USD".into()],
-                vec!["USD".into(), "USD".into()]
-            )
-        );
+    pub fn remove_anchor(&mut self, symbol: String) {
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        assert!(self.anchors.remove(&symbol).is_some(), "{}", Error::NotAnAnchor { symbol });
+        EVENTS.emit("remove_anchor", json!({ "symbol": symbol }));
     }
 
-    #[test]
-    fn test_relay_and_get_refs() {
-        let mut context = get_context();
-        context.predecessor_account_id = bob();
+    pub fn get_symbol_info(&self, symbol: String) -> Option<SymbolInfo> {
+        self.symbols.get(&symbol)
+    }
 
-        testing_env!(context.clone());
-        let mut contract = StdReferenceBasic::new();
+    /// Lists registered symbols. Deleting a symbol moves the last one into its position.
+    pub fn list_symbols(&self, from_index: u64, limit: u64) -> Vec<(String, SymbolInfo)> {
+        paginate(&self.symbols, from_index, limit)
+    }
 
-        contract.relay(
-            vec!["BTC".into(), "ETH".into()],
-            vec!["111000000000".into(), "222000000000".into()],
-            vec![333, 444],
-            vec![555, 666],
+    /// Registers `symbol` or updates its registration, which also lifts a deprecation.
+    /// A new multiplier only applies to rates relayed afterwards.
+    pub fn register_symbol(&mut self, symbol: String, multiplier: U128, description: String) {
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        assert!(multiplier.0 > 0, "{}", Error::ZeroMultiplier { symbol });
+        EVENTS.emit(
+            "register_symbol",
+            json!({"symbol": symbol, "multiplier": multiplier, "description": description}),
         );
+        self.symbols.insert(&symbol, &SymbolInfo { multiplier, description, deprecated: false });
+    }
 
-        assert_eq!(Some((111 * E9, 333, 555)), contract.get_refs("BTC".into()));
-        assert_eq!(Some((222 * E9, 444, 666)), contract.get_refs("ETH".into()));
+    pub fn deprecate_symbol(&mut self, symbol: String) {
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        let info = self.symbols.get(&symbol);
+        assert!(info.is_some(), "{}", Error::UnregisteredSymbol { symbol });
+        EVENTS.emit("deprecate_symbol", json!({ "symbol": symbol }));
+        self.symbols.insert(&symbol, &SymbolInfo { deprecated: true, ..info.unwrap() });
     }
 
-    #[test]
-    #[should_panic(expected = "NOT_AN_OWNER")]
-    fn test_relay_fail_because_not_owner() {
-        let mut context = get_context();
-        context.predecessor_account_id = alice();
+    /// Unregisters `symbol` and removes everything stored for it.
+    pub fn delete_symbol(&mut self, symbol: String) {
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        assert!(self.symbols.remove(&symbol).is_some(), "{}", Error::UnregisteredSymbol { symbol });
+        self.refs.remove(&symbol);
+        self.relay_order.remove(&symbol);
+        self.max_ages.remove(&symbol);
+        self.accumulators.remove(&symbol);
+        self.history.clear(&symbol);
+        self.rounds.clear(&symbol);
+        self.max_deviations.remove(&symbol);
+        self.pending_updates.remove(&symbol);
+        EVENTS.emit("delete_symbol", json!({ "symbol": symbol }));
+    }
 
-        testing_env!(context.clone());
-        let mut contract = StdReferenceBasic::new();
+    pub fn get_default_max_age(&self) -> u64 {
+        self.default_max_age
+    }
 
-        contract.relay(
-            vec!["BTC".into(), "ETH".into()],
-            vec!["111000000000".into(),"222000000000".into()],
-            vec![333, 444],
-            vec![555, 666],
+    pub fn set_default_max_age(&mut self, max_age: u64) {
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        EVENTS.emit(
+            "set_default_max_age",
+            json!({"old_max_age": U64(self.default_max_age), "new_max_age": U64(max_age)}),
         );
+        self.default_max_age = max_age;
     }
 
-    #[test]
-    #[should_panic(expected = "NOT_AN_OWNER")]
-    fn test_relay_fail_because_owner_has_changed() {
-        let mut context = get_context();
-        context.predecessor_account_id = bob();
+    pub fn get_max_age(&self, symbol: String) -> u64 {
+        self.max_ages.get(&symbol).unwrap_or(self.default_max_age)
+    }
 
-        testing_env!(context.clone());
-        let mut contract = StdReferenceBasic::new();
+    pub fn set_max_age(&mut self, symbol: String, max_age: u64) {
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        let registered = self.symbols.get(&symbol).is_some();
+        assert!(registered, "{}", Error::UnregisteredSymbol { symbol });
+        EVENTS.emit("set_max_age", json!({"symbol": symbol, "max_age": U64(max_age)}));
+        self.max_ages.insert(&symbol, &max_age);
+    }
 
-        // transfer ownership to alice
-        contract.transfer_ownership(alice());
+    pub fn remove_max_age(&mut self, symbol: String) {
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        assert!(self.max_ages.remove(&symbol).is_some(), "{}", Error::MaxAgeNotSet { symbol });
+        EVENTS.emit("remove_max_age", json!({ "symbol": symbol }));
+    }
 
-        contract.relay(
-            vec!["BTC".into(), "ETH".into()],
-            vec!["111000000000".into(),"222000000000".into()],
-            vec![333, 444],
-            vec![555, 666],
+    pub fn get_max_deviation(&self, symbol: String) -> Option<u64> {
+        self.max_deviations.get(&symbol)
+    }
+
+    /// Arms the circuit breaker of `symbol`. A relayed rate deviating from the stored one
+    /// by more than `max_deviation_bps` basis points is held back as a pending update,
+    /// until the guardian confirms it or another relayer relays a rate within the same
+    /// bound of it.
+    pub fn set_max_deviation(&mut self, symbol: String, max_deviation_bps: u64) {
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        let registered = self.symbols.get(&symbol).is_some();
+        assert!(registered, "{}", Error::UnregisteredSymbol { symbol });
+        EVENTS.emit(
+            "set_max_deviation",
+            json!({"symbol": symbol, "max_deviation_bps": U64(max_deviation_bps)}),
         );
+        self.max_deviations.insert(&symbol, &max_deviation_bps);
     }
 
-    #[test]
-    fn test_relay_and_get_reference_data() {
-        let mut context = get_context();
-        context.predecessor_account_id = bob();
+    /// Disarms the circuit breaker of `symbol`. A pending update stays pending.
+    pub fn remove_max_deviation(&mut self, symbol: String) {
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        let removed = self.max_deviations.remove(&symbol).is_some();
+        assert!(removed, "{}", Error::MaxDeviationNotSet { symbol });
+        EVENTS.emit("remove_max_deviation", json!({ "symbol": symbol }));
+    }
 
-        testing_env!(context.clone());
-        let mut contract = StdReferenceBasic::new();
+    pub fn get_pending_update(&self, symbol: String) -> Option<PendingUpdate> {
+        self.pending_updates.get(&symbol)
+    }
 
-        contract.relay(
-            vec!["BTC".into(), "ETH".into()],
-            vec!["111000000000".into(), "222000000000".into()],
-            vec![333, 444],
-            vec![555, 666],
-        );
+    pub fn list_pending_updates(
+        &self,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<(String, PendingUpdate)> {
+        paginate(&self.pending_updates, from_index, limit)
+    }
 
-        assert_eq!(
-            Some((111 * E9 * E9, 333, context.block_timestamp)),
-            contract.get_reference_data("BTC".into(), "USD".into())
-        );
-        assert_eq!(
-            Some((222 * E9 * E9, 444, context.block_timestamp)),
-            contract.get_reference_data("ETH".into(), "USD".into())
-        );
-        assert_eq!(
-            Some((E9 * E9 / 2, 333, 444)),
-            contract.get_reference_data("BTC".into(), "ETH".into())
+    /// Stores the pending update of `symbol` despite its deviation.
+    pub fn confirm_pending_update(&mut self, symbol: String) {
+        admin::assert_guardian(&self.owner, &self.guardian);
+        assert!(!self.paused, "{}", Error::Paused);
+        let pending = self.pending_updates.get(&symbol);
+        assert!(pending.is_some(), "{}", Error::NoPendingUpdateFor { symbol });
+        let pending = pending.unwrap();
+        EVENTS.emit(
+            "confirm_pending_update",
+            json!({"symbol": symbol, "by": env::predecessor_account_id()}),
         );
-        assert_eq!(
-            Some((2 * E9 * E9, 444, 333)),
-            contract.get_reference_data("ETH".into(), "BTC".into())
+        let mut events = RelayEvents::default();
+        let (rate, rt, rid) = (pending.rate.0, pending.resolve_time.0, pending.request_id.0);
+        self.write_ref(symbol, rate, rt, rid, &mut events);
+        events.emit();
+    }
+
+    pub fn reject_pending_update(&mut self, symbol: String) {
+        admin::assert_guardian(&self.owner, &self.guardian);
+        let removed = self.pending_updates.remove(&symbol).is_some();
+        assert!(removed, "{}", Error::NoPendingUpdateFor { symbol });
+        EVENTS.emit(
+            "reject_pending_update",
+            json!({"symbol": symbol, "by": env::predecessor_account_id()}),
         );
     }
 
-    #[test]
-    fn test_relay_and_get_reference_data_bulk() {
-        let mut context = get_context();
-        context.predecessor_account_id = bob();
+    pub fn get_history_depth(&self) -> u64 {
+        self.history.depth
+    }
 
-        testing_env!(context.clone());
-        let mut contract = StdReferenceBasic::new();
+    pub fn set_history_depth(&mut self, depth: u64) {
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        EVENTS.emit(
+            "set_history_depth",
+            json!({"old_depth": U64(self.history.depth), "new_depth": U64(depth)}),
+        );
+        self.history.depth = depth;
+    }
 
-        contract.relay(
-            vec!["BTC".into(), "ETH".into()],
+    pub fn get_history(&self, symbol: String, from: u64, limit: u64) -> Vec<HistoryEntry> {
+        assert!(!self.paused, "{}", Error::Paused);
+        self.history.range(&symbol, from, limit)
+    }
+
+    pub fn get_reference_data_at(
+        &self,
+        base: String,
+        quote: String,
+        timestamp: U64,
+    ) -> Option<ReferenceData> {
+        let ref_at = |symbol: &String| match self.anchor_refs(symbol, timestamp.0) {
+            Some(refs) => Ok(refs),
+            None => self
+                .history
+                .at(symbol, timestamp.0)
+                .map(|entry| (entry.rate.0, entry.resolve_time.0, entry.request_id.0))
+                .ok_or_else(|| Error::RefDataNotAvailableFor { symbol: symbol.clone() }),
+        };
+        logged(self.cross_rate(&base, &quote, ref_at(&base), ref_at(&quote)))
+            .map(|data| self.flag_deprecated(&base, &quote, data))
+    }
+
+    pub fn get_ema_period(&self) -> u64 {
+        self.ema_period
+    }
+
+    pub fn set_ema_period(&mut self, ema_period: u64) {
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        EVENTS.emit(
+            "set_ema_period",
+            json!({"old_ema_period": U64(self.ema_period), "new_ema_period": U64(ema_period)}),
+        );
+        self.ema_period = ema_period;
+    }
+
+    /// Time-weighted average over the last `window_ns` nanoseconds. Returns `None` when
+    /// the kept history does not reach back to the start of the window.
+    pub fn get_twap(&self, base: String, quote: String, window_ns: u64) -> Option<ReferenceData> {
+        assert!(window_ns > 0, "{}", Error::ZeroTwapWindow);
+        let now = env::block_timestamp();
+        let start = now.checked_sub(window_ns);
+        let twap_refs = |symbol: &String| {
+            self.averaged_refs(symbol, |acc| {
+                let not_enough_history = || Error::NotEnoughHistoryFor { symbol: symbol.clone() };
+                let start = start.ok_or_else(not_enough_history)?;
+                let entry = self.history.at(symbol, start).ok_or_else(not_enough_history)?;
+                let elapsed = (start - entry.relayed_at.0) as u128;
+                let cumulative = entry.cumulative.0.wrapping_add(entry.rate.0.wrapping_mul(elapsed));
+                let end = (acc.advance(now, self.ema_period).cumulative, now);
+                average::twap((cumulative, start), end).ok_or_else(not_enough_history)
+            })
+        };
+        logged(self.cross_rate(&base, &quote, twap_refs(&base), twap_refs(&quote)))
+            .map(|data| self.flag_deprecated(&base, &quote, data))
+    }
+
+    pub fn get_ema(&self, base: String, quote: String) -> Option<ReferenceData> {
+        let ema_refs = |symbol: &String| {
+            self.averaged_refs(symbol, |acc| {
+                Ok(acc.advance(env::block_timestamp(), self.ema_period).ema)
+            })
+        };
+        logged(self.cross_rate(&base, &quote, ema_refs(&base), ema_refs(&quote)))
+            .map(|data| self.flag_deprecated(&base, &quote, data))
+    }
+
+    /// Lists the relayed references at positions `from_index..from_index + limit` of the
+    /// order their symbols were first relayed in. Anchors are not included. A deleted
+    /// symbol leaves its position empty, so a page can hold fewer than `limit` references.
+    pub fn list_refs(&self, from_index: u64, limit: u64) -> Vec<(String, RefData)> {
+        assert!(!self.paused, "{}", Error::Paused);
+        let with_refs = |symbol: String| {
+            let refs = self.refs.get(&symbol).unwrap();
+            (symbol, RefData::from(refs))
+        };
+        self.relay_order.range(from_index, limit).into_iter().map(with_refs).collect()
+    }
+
+    /// Number of positions `list_refs` pages through, including those left empty by
+    /// deleted symbols.
+    pub fn count_refs(&self) -> u64 {
+        assert!(!self.paused, "{}", Error::Paused);
+        self.relay_order.len()
+    }
+
+    pub fn get_refs(&self, symbol: String) -> Option<(u128, u64, u64)> {
+        assert!(!self.paused, "{}", Error::Paused);
+        self.stored_refs(&symbol)
+    }
+
+    pub fn get_reference_data_fresh(
+        &self,
+        base: String,
+        quote: String,
+        max_age_ns: Option<u64>,
+    ) -> Option<(u128, u64, u64)> {
+        logged(self.reference_data_fresh(&base, &quote, max_age_ns))
+    }
+
+    /// Like `get_reference_data_v2`, but tells which symbol failed and why.
+    pub fn try_get_reference_data(
+        &self,
+        base: String,
+        quote: String,
+    ) -> Result<ReferenceData, Error> {
+        let data = self.reference_data(&base, &quote)?;
+        Ok(self.flag_deprecated(&base, &quote, data))
+    }
+
+    /// Like `get_reference_data_fresh_v2`, but tells which symbol failed and why.
+    pub fn try_get_reference_data_fresh(
+        &self,
+        base: String,
+        quote: String,
+        max_age_ns: Option<u64>,
+    ) -> Result<ReferenceData, Error> {
+        let data = self.reference_data_fresh(&base, &quote, max_age_ns)?;
+        Ok(self.flag_deprecated(&base, &quote, data))
+    }
+
+    pub fn get_refs_v2(&self, symbol: String) -> Option<RefData> {
+        self.get_refs(symbol).map(RefData::from)
+    }
+
+    pub fn get_reference_data_fresh_v2(
+        &self,
+        base: String,
+        quote: String,
+        max_age_ns: Option<u64>,
+    ) -> Option<ReferenceData> {
+        logged(self.reference_data_fresh(&base, &quote, max_age_ns))
+            .map(|data| self.flag_deprecated(&base, &quote, data))
+    }
+
+    pub fn relay(
+        &mut self,
+        symbols: Vec<String>,
+        rates: Vec<String>,
+        resolve_times: Vec<u64>,
+        request_ids: Vec<u64>,
+    ) {
+        assert!(self.is_relayer(env::predecessor_account_id()), "{}", Error::NotARelayer);
+        assert!(!self.paused, "{}", Error::Paused);
+        self.relay_internal(symbols, rates, resolve_times, request_ids, false);
+    }
+
+    pub fn force_relay(
+        &mut self,
+        symbols: Vec<String>,
+        rates: Vec<String>,
+        resolve_times: Vec<u64>,
+        request_ids: Vec<u64>,
+    ) {
+        assert!(env::predecessor_account_id() == self.get_owner(), "{}", Error::NotAnOwner);
+        self.relay_internal(symbols, rates, resolve_times, request_ids, true);
+    }
+
+    /// Submits rates to the open round of each symbol. Only submissions of current
+    /// feeders made within the round timeout count. Once a round reaches the quorum, the
+    /// median of its rates is stored like a relayed rate, with the median of its resolve
+    /// times and the round id as request id.
+    pub fn submit(&mut self, symbols: Vec<String>, rates: Vec<String>, resolve_times: Vec<u64>) {
+        let feeder = env::predecessor_account_id();
+        assert!(self.is_feeder(feeder.clone()), "{}", Error::NotAFeeder);
+        assert!(!self.paused, "{}", Error::Paused);
+        let len = symbols.len();
+        assert!(rates.len() == len, "{}", Error::BadRatesLength);
+        assert!(resolve_times.len() == len, "{}", Error::BadResolveTimesLength);
+
+        let (mut submitted, mut finalized) = (vec![], vec![]);
+        let mut events = RelayEvents::default();
+        for (s, (r, rt)) in zip!(&symbols, &rates, &resolve_times) {
+            let rate = self.normalized_rate(&s, &r);
+            let submission = Submission {
+                feeder: feeder.clone(),
+                rate: rate.into(),
+                resolve_time: rt.into(),
+                submitted_at: env::block_timestamp().into(),
+            };
+            let feeders = &self.feeders;
+            let (round, complete) =
+                self.rounds.submit(&s, &submission, |account| feeders.contains(account));
+            submitted.push(json!({
+                "symbol": s,
+                "round_id": U64(round),
+                "feeder": feeder,
+                "rate": U128(rate),
+                "resolve_time": U64(rt),
+            }));
+            if let Some(submissions) = complete {
+                let rates = submissions.iter().map(|submission| submission.rate.0).collect();
+                let rate = rounds::median(rates).unwrap();
+                let rts = submissions.iter().map(|submission| submission.resolve_time.0 as u128);
+                let rt = rounds::median(rts.collect()).unwrap() as u64;
+                finalized.push(json!({
+                    "symbol": s,
+                    "round_id": U64(round),
+                    "submissions": submissions.len(),
+                }));
+                self.store_ref(s, rate, rt, round, false, &mut events);
+            }
+        }
+        EVENTS.emit("submit", submitted.into());
+        if !finalized.is_empty() {
+            EVENTS.emit("finalize_round", finalized.into());
+        }
+        events.emit();
+    }
+
+    /// Stores the rates of a BandChain oracle result once `proof` shows that it was
+    /// signed off by the validator set. Anyone may relay a proof. Rates are normalized
+    /// by the multiplier the request was made with, and resolve times are converted
+    /// from seconds to nanoseconds.
+    pub fn relay_with_proof(&mut self, proof: Proof) {
+        assert!(!self.paused, "{}", Error::Paused);
+        let block_height = self.bridge.verify(&proof).unwrap_or_else(|e| panic!("{}", e));
+        let result = &proof.oracle_data_proof.result;
+        let (symbols, multiplier, rates) = result.prices().unwrap_or_else(|e| panic!("{}", e));
+        let rt = result.resolve_time.0.checked_mul(1_000_000_000);
+        assert!(rt.is_some(), "{}", Error::MalformedProof);
+        EVENTS.emit(
+            "relay_with_proof",
+            json!({
+                "relayer": env::predecessor_account_id(),
+                "block_height": U64(block_height),
+                "request_id": result.request_id,
+            }),
+        );
+
+        let mut events = RelayEvents::default();
+        for (s, r) in symbols.into_iter().zip(rates) {
+            self.relayable_symbol(&s);
+            let rate = normalize(&s, r as u128, multiplier as u128);
+            self.store_ref(s, rate, rt.unwrap(), result.request_id.0, false, &mut events);
+        }
+        events.emit();
+    }
+}
+
+#[near_bindgen]
+impl StdReference for StdReferenceBasic {
+    fn get_reference_data(&self, base: String, quote: String) -> Option<(u128, u64, u64)> {
+        logged(self.reference_data(&base, &quote))
+    }
+
+    fn get_reference_data_bulk(
+        &self,
+        bases: Vec<String>,
+        quotes: Vec<String>,
+    ) -> Option<Vec<(u128, u64, u64)>> {
+        assert!(bases.len() == quotes.len(), "{}", Error::BadInputLength);
+        bases
+            .iter()
+            .zip(quotes.iter())
+            .map(|(b, q)| self.get_reference_data(b.clone(), q.clone()))
+            .collect()
+    }
+
+    fn get_reference_data_v2(&self, base: String, quote: String) -> Option<ReferenceData> {
+        logged(self.reference_data(&base, &quote))
+            .map(|data| self.flag_deprecated(&base, &quote, data))
+    }
+
+    fn get_reference_data_bulk_v2(
+        &self,
+        bases: Vec<String>,
+        quotes: Vec<String>,
+    ) -> Option<Vec<ReferenceData>> {
+        assert!(bases.len() == quotes.len(), "{}", Error::BadInputLength);
+        bases
+            .iter()
+            .zip(quotes.iter())
+            .map(|(b, q)| self.get_reference_data_v2(b.clone(), q.clone()))
+            .collect()
+    }
+
+    fn get_reference_data_bulk_partial(
+        &self,
+        bases: Vec<String>,
+        quotes: Vec<String>,
+    ) -> Vec<Option<ReferenceData>> {
+        assert!(bases.len() == quotes.len(), "{}", Error::BadInputLength);
+        bases
+            .iter()
+            .zip(quotes.iter())
+            .map(|(b, q)| self.get_reference_data_v2(b.clone(), q.clone()))
+            .collect()
+    }
+}
+
+impl StdReferenceBasic {
+    fn with_owner(owner: AccountId) -> Self {
+        let mut contract = Self {
+            refs: UnorderedMap::new(b"refs".to_vec()),
+            owner,
+            relayers: UnorderedSet::new(b"relayers".to_vec()),
+            default_max_age: 0,
+            max_ages: UnorderedMap::new(b"max_ages".to_vec()),
+            history: History::new(b"history", DEFAULT_HISTORY_DEPTH),
+            accumulators: UnorderedMap::new(b"accumulators".to_vec()),
+            ema_period: DEFAULT_EMA_PERIOD,
+            anchors: UnorderedMap::new(b"anchors".to_vec()),
+            symbols: UnorderedMap::new(b"symbols".to_vec()),
+            pending_owner: None,
+            feeders: UnorderedSet::new(b"feeders".to_vec()),
+            rounds: Rounds::new(b"rounds", DEFAULT_QUORUM, DEFAULT_ROUND_TIMEOUT),
+            guardian: None,
+            max_deviations: UnorderedMap::new(b"max_deviations".to_vec()),
+            pending_updates: UnorderedMap::new(b"pending_updates".to_vec()),
+            paused: false,
+            bridge: Bridge::new(b"bridge"),
+            relay_order: RelayOrder::new(b"relay_order"),
+        };
+        contract.anchors.insert(&"USD".to_string(), &E9);
+        contract
+    }
+
+    // The owner, who was the only one allowed to relay, stays a relayer. Relayed symbols
+    // are registered with the `E9` multiplier their rates were relayed with, and their
+    // averages start from the stored rates.
+    fn from_unversioned(old: OldStdReferenceBasic) -> Self {
+        let mut contract = Self::with_owner(old.owner);
+        contract.refs = old.refs;
+        contract.relayers.insert(&contract.owner);
+        let now = env::block_timestamp();
+        for (symbol, (rate, _, _)) in contract.refs.iter() {
+            let info =
+                SymbolInfo { multiplier: E9.into(), description: String::new(), deprecated: false };
+            contract.symbols.insert(&symbol, &info);
+            contract.accumulators.insert(&symbol, &Accumulator::new(rate, now));
+            contract.relay_order.insert(&symbol);
+        }
+        contract
+    }
+
+    fn from_v1(old: StdReferenceBasicV1) -> Self {
+        Self::from_v2(StdReferenceBasicV2 {
+            refs: old.refs,
+            owner: old.owner,
+            relayers: old.relayers,
+            default_max_age: old.default_max_age,
+            max_ages: old.max_ages,
+            history: old.history,
+            accumulators: old.accumulators,
+            ema_period: old.ema_period,
+            anchors: old.anchors,
+            symbols: old.symbols,
+            pending_owner: old.pending_owner,
+            feeders: old.feeders,
+            rounds: old.rounds,
+            guardian: old.guardian,
+            max_deviations: old.max_deviations,
+            pending_updates: old.pending_updates,
+            paused: old.paused,
+            bridge: Bridge::new(b"bridge"),
+        })
+    }
+
+    // Symbols keep their position in `list_refs`.
+    fn from_v2(old: StdReferenceBasicV2) -> Self {
+        let mut relay_order = RelayOrder::new(b"relay_order");
+        for symbol in old.refs.keys() {
+            relay_order.insert(&symbol);
+        }
+        Self {
+            refs: old.refs,
+            owner: old.owner,
+            relayers: old.relayers,
+            default_max_age: old.default_max_age,
+            max_ages: old.max_ages,
+            history: old.history,
+            accumulators: old.accumulators,
+            ema_period: old.ema_period,
+            anchors: old.anchors,
+            symbols: old.symbols,
+            pending_owner: old.pending_owner,
+            feeders: old.feeders,
+            rounds: old.rounds,
+            guardian: old.guardian,
+            max_deviations: old.max_deviations,
+            pending_updates: old.pending_updates,
+            paused: old.paused,
+            bridge: old.bridge,
+            relay_order,
+        }
+    }
+
+    // Anchors have a fixed rate that is always up to date.
+    fn anchor_refs(&self, symbol: &str, timestamp: u64) -> Option<(u128, u64, u64)> {
+        self.anchors.get(&symbol.to_string()).map(|rate| (rate, timestamp, 0))
+    }
+
+    fn stored_refs(&self, symbol: &str) -> Option<(u128, u64, u64)> {
+        match self.anchor_refs(symbol, env::block_timestamp()) {
+            Some(refs) => Some(refs),
+            None => self.refs.get(&symbol.to_string()),
+        }
+    }
+
+    fn is_deprecated(&self, symbol: &str) -> bool {
+        self.symbols.get(&symbol.to_string()).is_some_and(|info| info.deprecated)
+    }
+
+    // Tells consumers of the v2 views that a symbol of the pair is no longer updated.
+    fn flag_deprecated(&self, base: &str, quote: &str, data: (u128, u64, u64)) -> ReferenceData {
+        ReferenceData {
+            deprecated_base: self.is_deprecated(base),
+            deprecated_quote: self.is_deprecated(quote),
+            ..data.into()
+        }
+    }
+
+    fn reference_data(&self, base: &str, quote: &str) -> Result<(u128, u64, u64), Error> {
+        let refs = |symbol: &str| {
+            self.stored_refs(symbol)
+                .ok_or_else(|| Error::RefDataNotAvailableFor { symbol: symbol.to_string() })
+        };
+        self.cross_rate(base, quote, refs(base), refs(quote))
+    }
+
+    fn reference_data_fresh(
+        &self,
+        base: &str,
+        quote: &str,
+        max_age_ns: Option<u64>,
+    ) -> Result<(u128, u64, u64), Error> {
+        let (rate, bt, qt) = self.reference_data(base, quote)?;
+        for (symbol, resolve_time) in &[(base, bt), (quote, qt)] {
+            if !self.is_fresh(symbol, *resolve_time, max_age_ns) {
+                return Err(Error::RefDataTooOldFor { symbol: symbol.to_string() });
+            }
+        }
+        Ok((rate, bt, qt))
+    }
+
+    fn cross_rate(
+        &self,
+        base: &str,
+        quote: &str,
+        base_ref: Result<(u128, u64, u64), Error>,
+        quote_ref: Result<(u128, u64, u64), Error>,
+    ) -> Result<(u128, u64, u64), Error> {
+        if self.paused {
+            return Err(Error::Paused);
+        }
+        let ((br, bt, _), (qr, qt, _)) = (base_ref?, quote_ref?);
+        match math::mul_div(br, E9 * E9, qr) {
+            Some(rate) => Ok((rate, bt, qt)),
+            None if qr == 0 => Err(Error::ZeroRateFor { symbol: quote.to_string() }),
+            None => Err(Error::RateOverflowFor { symbol: format!("{}/{}", base, quote) }),
+        }
+    }
+
+    // Replaces the relayed rate of `symbol` by an average derived from its accumulator,
+    // keeping the resolve time and request id of the latest relay.
+    fn averaged_refs(
+        &self,
+        symbol: &str,
+        average: impl Fn(Accumulator) -> Result<u128, Error>,
+    ) -> Result<(u128, u64, u64), Error> {
+        if let Some(refs) = self.anchor_refs(symbol, env::block_timestamp()) {
+            return Ok(refs);
+        }
+        let not_available = || Error::RefDataNotAvailableFor { symbol: symbol.to_string() };
+        let (_, rt, rid) = self.refs.get(&symbol.to_string()).ok_or_else(not_available)?;
+        let accumulator = self.accumulators.get(&symbol.to_string()).ok_or_else(not_available)?;
+        Ok((average(accumulator)?, rt, rid))
+    }
+
+    // A max age of 0 means that no limit is applied.
+    fn is_fresh(&self, symbol: &str, resolve_time: u64, max_age_ns: Option<u64>) -> bool {
+        let age = env::block_timestamp().saturating_sub(resolve_time);
+        let configured = Some(self.get_max_age(symbol.to_string()));
+        [configured, max_age_ns].iter().flatten().all(|&max_age| max_age == 0 || age <= max_age)
+    }
+
+    fn relay_internal(
+        &mut self,
+        symbols: Vec<String>,
+        rates: Vec<String>,
+        resolve_times: Vec<u64>,
+        request_ids: Vec<u64>,
+        force: bool,
+    ) {
+        let len = symbols.len();
+        assert!(rates.len() == len, "{}", Error::BadRatesLength);
+        assert!(resolve_times.len() == len, "{}", Error::BadResolveTimesLength);
+        assert!(request_ids.len() == len, "{}", Error::BadRequestIdsLength);
+
+        let mut events = RelayEvents::default();
+        for (s, (r, (rt, rid))) in zip!(&symbols, &rates, &resolve_times, &request_ids) {
+            let rate = self.normalized_rate(&s, &r);
+            self.store_ref(s, rate, rt, rid, force, &mut events);
+        }
+        events.emit();
+    }
+
+    // Parses a relayed or submitted `rate` and normalizes it by the multiplier of `symbol`.
+    fn normalized_rate(&self, symbol: &str, rate: &str) -> u128 {
+        let rate_opt = rate.parse::<u128>().ok();
+        assert!(
+            rate_opt.is_some(),
+            "{}", Error::FailToParseRate { symbol: symbol.to_string(), rate: rate.to_string() }
+        );
+        let multiplier = self.relayable_symbol(symbol).multiplier.0;
+        normalize(symbol, rate_opt.unwrap(), multiplier)
+    }
+
+    // Panics unless rates may be relayed for `symbol`.
+    fn relayable_symbol(&self, symbol: &str) -> SymbolInfo {
+        let s = symbol.to_string();
+        assert!(self.anchors.get(&s).is_none(), "{}", Error::CannotRelayAnchor { symbol: s });
+        let info = self.symbols.get(&s);
+        assert!(info.is_some(), "{}", Error::UnregisteredSymbol { symbol: s });
+        let deprecated = info.as_ref().unwrap().deprecated;
+        assert!(!deprecated, "{}", Error::DeprecatedSymbol { symbol: s });
+        info.unwrap()
+    }
+
+    // Stores a normalized rate. Unless `force` is on, a rate that is not newer than the
+    // stored one is skipped and one that trips the circuit breaker is held back.
+    fn store_ref(
+        &mut self,
+        s: String,
+        rate: u128,
+        rt: u64,
+        rid: u64,
+        force: bool,
+        events: &mut RelayEvents,
+    ) {
+        if !force {
+            if let Some((_, last_rt, _)) = self.refs.get(&s) {
+                if rt <= last_rt {
+                    events.skipped.push(json!({
+                        "symbol": s,
+                        "resolve_time": U64(rt),
+                        "request_id": U64(rid),
+                    }));
+                    return;
+                }
+            }
+            if let Some(previous_rate) = self.tripped_breaker(&s, rate) {
+                events.tripped.push(json!({
+                    "symbol": s,
+                    "rate": U128(rate),
+                    "previous_rate": U128(previous_rate),
+                    "resolve_time": U64(rt),
+                    "request_id": U64(rid),
+                }));
+                self.pending_updates.insert(&s, &PendingUpdate {
+                    rate: rate.into(),
+                    resolve_time: rt.into(),
+                    request_id: rid.into(),
+                    previous_rate: previous_rate.into(),
+                    relayed_at: env::block_timestamp().into(),
+                    relayer: env::predecessor_account_id(),
+                });
+                return;
+            }
+        }
+        self.write_ref(s, rate, rt, rid, events);
+    }
+
+    // Returns the stored rate of `symbol` if `rate` deviates too much from it and does
+    // not corroborate the pending update either. Only a relayer other than the one that
+    // relayed the pending update can corroborate it.
+    fn tripped_breaker(&self, symbol: &str, rate: u128) -> Option<u128> {
+        let symbol = symbol.to_string();
+        let max_deviation = self.max_deviations.get(&symbol)? as u128;
+        let (previous_rate, _, _) = self.refs.get(&symbol)?;
+        let deviates =
+            |from: u128| math::deviation_bps(from, rate).is_none_or(|bps| bps > max_deviation);
+        let predecessor = env::predecessor_account_id();
+        let corroborates = self.pending_updates.get(&symbol).is_some_and(|p| {
+            p.relayer != predecessor && self.is_relayer(predecessor.clone()) && !deviates(p.rate.0)
+        });
+        Some(previous_rate).filter(|&previous_rate| deviates(previous_rate) && !corroborates)
+    }
+
+    fn write_ref(&mut self, s: String, rate: u128, rt: u64, rid: u64, events: &mut RelayEvents) {
+        let now = env::block_timestamp();
+        let accumulator = match self.accumulators.get(&s) {
+            Some(mut accumulator) => {
+                accumulator.update(rate, now, self.ema_period);
+                accumulator
+            }
+            None => Accumulator::new(rate, now),
+        };
+        self.accumulators.insert(&s, &accumulator);
+        self.refs.insert(&s, &(rate, rt, rid));
+        self.relay_order.insert(&s);
+        self.pending_updates.remove(&s);
+        self.history.push(&s, &HistoryEntry {
+            rate: rate.into(),
+            resolve_time: rt.into(),
+            request_id: rid.into(),
+            relayed_at: now.into(),
+            cumulative: accumulator.cumulative.into(),
+        });
+        events.relayed.push(json!({
+            "symbol": s,
+            "rate": U128(rate),
+            "resolve_time": U64(rt),
+            "request_id": U64(rid),
+        }));
+    }
+}
+
+// The `relay`, `relay_skip` and `breaker_trip` events of one call, emitted together
+// at its end.
+#[derive(Default)]
+struct RelayEvents {
+    relayed: Vec<Value>,
+    skipped: Vec<Value>,
+    tripped: Vec<Value>,
+}
+
+impl RelayEvents {
+    fn emit(self) {
+        if !self.relayed.is_empty() {
+            EVENTS.emit("relay", self.relayed.into());
+        }
+        if !self.skipped.is_empty() {
+            EVENTS.emit("relay_skip", self.skipped.into());
+        }
+        if !self.tripped.is_empty() {
+            EVENTS.emit("breaker_trip", self.tripped.into());
+        }
+    }
+}
+
+fn normalize(symbol: &str, rate: u128, multiplier: u128) -> u128 {
+    let rate = math::mul_div(rate, E9, multiplier);
+    assert!(rate.is_some(), "{}", Error::RateOverflowFor { symbol: symbol.to_string() });
+    rate.unwrap()
+}
+
+fn log(error: &Error) {
+    env::log(error.to_string().as_bytes());
+}
+
+// Views that return an `Option` log why they return `None`. While paused they fail
+// instead, so that callers cannot mistake the pause for missing data.
+fn logged<T>(result: Result<T, Error>) -> Option<T> {
+    match result {
+        Err(Error::Paused) => panic!("{}", Error::Paused),
+        result => result.map_err(|error| log(&error)).ok(),
+    }
+}
+
+// use the attribute below for unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::MockedBlockchain;
+    use serde_json::Value;
+    use std_reference_interface::events::{take_emitted, EVENT_JSON_PREFIX};
+    use near_sdk::testing_env;
+    use test_utils::get_context;
+
+    fn alice() -> AccountId {
+        "alice.near".to_string()
+    }
+
+    fn bob() -> AccountId {
+        "bob.near".to_string()
+    }
+
+    fn carol() -> AccountId {
+        "carol.near".to_string()
+    }
+
+    fn emitted_events() -> Vec<Value> {
+        take_emitted()
+            .iter()
+            .map(|log| serde_json::from_str(&log[EVENT_JSON_PREFIX.len()..]).unwrap())
+            .collect()
+    }
+
+    // Reads an entry of the proof fixtures, see `fixtures/generate_proofs.py`.
+    fn fixture<T: serde::de::DeserializeOwned>(name: &str) -> T {
+        let fixtures = include_str!("../fixtures/proofs.json");
+        serde_json::from_value(serde_json::from_str::<Value>(fixtures).unwrap()[name].clone())
+            .unwrap()
+    }
+
+    // Configures the bridge with the test validator set the fixtures are signed by.
+    fn set_up_bridge(contract: &mut StdReferenceBasic) {
+        contract.set_bridge_config(BridgeConfig {
+            chain_id: fixture("chain_id"),
+            oracle_script_id: fixture("oracle_script_id"),
+        });
+        contract.update_validator_powers(fixture("validators"));
+        register(contract, &["BTC", "ETH"]);
+        take_emitted();
+    }
+
+    fn register(contract: &mut StdReferenceBasic, symbols: &[&str]) {
+        for symbol in symbols {
+            let info = SymbolInfo {
+                multiplier: U128(E9),
+                description: symbol.to_string(),
+                deprecated: false,
+            };
+            contract.symbols.insert(&symbol.to_string(), &info);
+        }
+    }
+
+    #[test]
+    #[allow(clippy::bool_assert_comparison)]
+    fn test_create_new_contract() {
+        let context = get_context();
+        testing_env!(context);
+        let contract = StdReferenceBasic::new();
+
+        // check state
+        assert_eq!(bob(), contract.owner);
+        assert_eq!(true, contract.refs.is_empty());
+
+        // check owner using view function
+        assert_eq!(bob(), contract.get_owner());
+    }
+
+    // The transfer itself is tested in `std_ref_interface::admin`.
+    #[test]
+    fn test_transfer_ownership() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+
+        contract.propose_ownership(alice());
+        contract.cancel_ownership_proposal();
+        assert_eq!(None, contract.get_pending_owner());
+        contract.propose_ownership(alice());
+        assert_eq!(Some(alice()), contract.get_pending_owner());
+
+        context.predecessor_account_id = alice();
+        context.storage_usage = env::storage_usage();
+        testing_env!(context);
+        contract.accept_ownership();
+        assert_eq!(alice(), contract.get_owner());
+        assert_eq!(None, contract.get_pending_owner());
+    }
+
+    #[test]
+    fn test_add_and_remove_relayer() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+
+        assert!(!contract.is_relayer(carol()));
+        assert_eq!(Vec::<AccountId>::new(), contract.get_relayers());
+
+        contract.add_relayer(carol());
+        contract.add_relayer(alice());
+        assert!(contract.is_relayer(carol()));
+        assert!(contract.is_relayer(alice()));
+        assert_eq!(vec![carol(), alice()], contract.get_relayers());
+
+        contract.remove_relayer(carol());
+        assert!(!contract.is_relayer(carol()));
+        assert_eq!(vec![alice()], contract.get_relayers());
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_add_relayer_fail_because_not_owner() {
+        let mut context = get_context();
+        context.predecessor_account_id = carol();
+
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+
+        contract.add_relayer(carol());
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_remove_relayer_fail_because_not_owner() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+        contract.add_relayer(carol());
+
+        context.predecessor_account_id = carol();
+        testing_env!(context);
+        contract.remove_relayer(carol());
+    }
+
+    #[test]
+    #[should_panic(expected = "ALREADY_A_RELAYER")]
+    fn test_add_relayer_fail_because_already_a_relayer() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+
+        contract.add_relayer(carol());
+        contract.add_relayer(carol());
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_A_RELAYER")]
+    fn test_remove_relayer_fail_because_not_a_relayer() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+
+        contract.remove_relayer(carol());
+    }
+
+    #[test]
+    fn test_relay_by_relayer_who_is_not_owner() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC"]);
+        contract.add_relayer(carol());
+
+        context.predecessor_account_id = carol();
+        testing_env!(context);
+        contract.relay(
+            vec!["BTC".into()],
+            vec!["111000000000".into()],
+            vec![333],
+            vec![555],
+        );
+
+        assert_eq!(Some((111 * E9, 333, 555)), contract.get_refs("BTC".into()));
+    }
+
+    #[test]
+    fn test_get_refs_usd() {
+        let context = get_context();
+        testing_env!(context.clone());
+        let contract = StdReferenceBasic::new();
+
+        assert_eq!(
+            Some((E9, context.block_timestamp, 0)),
+            contract.get_refs("USD".into())
+        );
+    }
+
+    #[test]
+    fn test_get_refs_symbol_not_found() {
+        let context = get_context();
+        testing_env!(context.clone());
+        let contract = StdReferenceBasic::new();
+
+        assert_eq!(None, contract.get_refs("BTC".into()));
+    }
+
+    #[test]
+    fn test_get_reference_data_usd() {
+        let context = get_context();
+        testing_env!(context.clone());
+        let contract = StdReferenceBasic::new();
+
+        assert_eq!(
+            Some((E9 * E9, context.block_timestamp, context.block_timestamp)),
+            contract.get_reference_data("USD".into(), "USD".into())
+        );
+    }
+
+    #[test]
+    fn test_get_reference_data_not_found() {
+        let context = get_context();
+        testing_env!(context.clone());
+        let contract = StdReferenceBasic::new();
+
+        assert_eq!(
+            None,
+            contract.get_reference_data("BTC".into(), "USD".into())
+        );
+    }
+
+    #[test]
+    fn test_get_reference_data_bulk_usd() {
+        let context = get_context();
+        testing_env!(context.clone());
+        let contract = StdReferenceBasic::new();
+
+        assert_eq!(
+            Some(vec![
+                (E9 * E9, context.block_timestamp, context.block_timestamp),
+                (E9 * E9, context.block_timestamp, context.block_timestamp)
+            ]),
+            contract.get_reference_data_bulk(
+                vec!["USD".into(), "USD".into()],
+                vec!["USD".into(), "USD".into()]
+            )
+        );
+    }
+
+    #[test]
+    fn test_get_reference_data_bulk_not_found() {
+        let context = get_context();
+        testing_env!(context.clone());
+        let contract = StdReferenceBasic::new();
+
+        assert_eq!(
+            None,
+            contract.get_reference_data_bulk(
+                vec!["BTC".into(), "USD".into()],
+                vec!["USD".into(), "USD".into()]
+            )
+        );
+    }
+
+    #[test]
+    fn test_relay_and_get_refs() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC", "ETH"]);
+        contract.add_relayer(bob());
+
+        contract.relay(
+            vec!["BTC".into(), "ETH".into()],
+            vec!["111000000000".into(), "222000000000".into()],
+            vec![333, 444],
+            vec![555, 666],
+        );
+
+        assert_eq!(Some((111 * E9, 333, 555)), contract.get_refs("BTC".into()));
+        assert_eq!(Some((222 * E9, 444, 666)), contract.get_refs("ETH".into()));
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_A_RELAYER")]
+    fn test_relay_fail_because_not_relayer() {
+        let mut context = get_context();
+        context.predecessor_account_id = alice();
+
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+
+        contract.relay(
+            vec!["BTC".into(), "ETH".into()],
+            vec!["111000000000".into(),"222000000000".into()],
+            vec![333, 444],
+            vec![555, 666],
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_A_RELAYER")]
+    fn test_relay_fail_because_relayer_has_been_removed() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+
+        // add bob as a relayer and then remove him
+        contract.add_relayer(bob());
+        contract.remove_relayer(bob());
+
+        contract.relay(
+            vec!["BTC".into(), "ETH".into()],
+            vec!["111000000000".into(),"222000000000".into()],
+            vec![333, 444],
+            vec![555, 666],
+        );
+    }
+
+    #[test]
+    fn test_relay_and_get_reference_data() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC", "ETH"]);
+        contract.add_relayer(bob());
+
+        contract.relay(
+            vec!["BTC".into(), "ETH".into()],
+            vec!["111000000000".into(), "222000000000".into()],
+            vec![333, 444],
+            vec![555, 666],
+        );
+
+        assert_eq!(
+            Some((111 * E9 * E9, 333, context.block_timestamp)),
+            contract.get_reference_data("BTC".into(), "USD".into())
+        );
+        assert_eq!(
+            Some((222 * E9 * E9, 444, context.block_timestamp)),
+            contract.get_reference_data("ETH".into(), "USD".into())
+        );
+        assert_eq!(
+            Some((E9 * E9 / 2, 333, 444)),
+            contract.get_reference_data("BTC".into(), "ETH".into())
+        );
+        assert_eq!(
+            Some((2 * E9 * E9, 444, 333)),
+            contract.get_reference_data("ETH".into(), "BTC".into())
+        );
+    }
+
+    #[test]
+    fn test_relay_and_get_reference_data_bulk() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC", "ETH"]);
+        contract.add_relayer(bob());
+
+        contract.relay(
+            vec!["BTC".into(), "ETH".into()],
+            vec!["111000000000".into(), "222000000000".into()],
+            vec![333, 444],
+            vec![555, 666],
+        );
+
+        assert_eq!(
+            Some(vec![
+                (111 * E9 * E9, 333, context.block_timestamp),
+                (222 * E9 * E9, 444, context.block_timestamp),
+                (E9 * E9 / 2, 333, 444),
+                (2 * E9 * E9, 444, 333),
+            ]),
+            contract.get_reference_data_bulk(
+                vec!["BTC".into(), "ETH".into(), "BTC".into(), "ETH".into()],
+                vec!["USD".into(), "USD".into(), "ETH".into(), "BTC".into()]
+            )
+        );
+    }
+
+    #[test]
+    fn test_relay_skip_stale_resolve_time() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC", "ETH"]);
+        contract.add_relayer(bob());
+
+        contract.relay(
+            vec!["BTC".into(), "ETH".into()],
+            vec!["111000000000".into(), "222000000000".into()],
+            vec![333, 444],
+            vec![555, 666],
+        );
+
+        // BTC is older and ETH has the same resolve time, both must be skipped
+        contract.relay(
+            vec!["BTC".into(), "ETH".into()],
+            vec!["1000000000".into(), "2000000000".into()],
+            vec![332, 444],
+            vec![777, 888],
+        );
+
+        assert_eq!(Some((111 * E9, 333, 555)), contract.get_refs("BTC".into()));
+        assert_eq!(Some((222 * E9, 444, 666)), contract.get_refs("ETH".into()));
+    }
+
+    #[test]
+    fn test_relay_skip_only_stale_symbols() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC", "ETH"]);
+        contract.add_relayer(bob());
+
+        contract.relay(
+            vec!["BTC".into(), "ETH".into()],
+            vec!["111000000000".into(), "222000000000".into()],
+            vec![333, 444],
+            vec![555, 666],
+        );
+
+        // only ETH is newer than the stored value
+        contract.relay(
+            vec!["BTC".into(), "ETH".into()],
+            vec!["1000000000".into(), "2000000000".into()],
+            vec![300, 445],
+            vec![777, 888],
+        );
+
+        assert_eq!(Some((111 * E9, 333, 555)), contract.get_refs("BTC".into()));
+        assert_eq!(Some((2 * E9, 445, 888)), contract.get_refs("ETH".into()));
+    }
+
+    #[test]
+    fn test_force_relay_overwrite_stale_resolve_time() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC"]);
+        contract.add_relayer(bob());
+
+        contract.relay(
+            vec!["BTC".into()],
+            vec!["111000000000".into()],
+            vec![333],
+            vec![555],
+        );
+
+        contract.force_relay(
+            vec!["BTC".into()],
+            vec!["1000000000".into()],
+            vec![222],
+            vec![777],
+        );
+
+        assert_eq!(Some((E9, 222, 777)), contract.get_refs("BTC".into()));
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_force_relay_fail_because_not_owner() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+        contract.add_relayer(carol());
+
+        // being a relayer is not enough to force relay
+        context.predecessor_account_id = carol();
+        testing_env!(context);
+        contract.force_relay(
+            vec!["BTC".into()],
+            vec!["111000000000".into()],
+            vec![333],
+            vec![555],
+        );
+    }
+
+    #[test]
+    fn test_set_max_age() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC"]);
+
+        assert_eq!(0, contract.get_default_max_age());
+        assert_eq!(0, contract.get_max_age("BTC".into()));
+
+        contract.set_default_max_age(100);
+        contract.set_max_age("BTC".into(), 10);
+        assert_eq!(100, contract.get_default_max_age());
+        assert_eq!(10, contract.get_max_age("BTC".into()));
+        assert_eq!(100, contract.get_max_age("ETH".into()));
+
+        contract.remove_max_age("BTC".into());
+        assert_eq!(100, contract.get_max_age("BTC".into()));
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_set_default_max_age_fail_because_not_owner() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+
+        contract.set_default_max_age(100);
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_set_max_age_fail_because_not_owner() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+
+        contract.set_max_age("BTC".into(), 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "UNREGISTERED_SYMBOL:BTC")]
+    fn test_set_max_age_fail_because_unregistered() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+
+        contract.set_max_age("BTC".into(), 10);
+    }
+
+    #[test]
+    fn test_get_reference_data_fresh() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC", "ETH"]);
+        contract.add_relayer(bob());
+
+        contract.relay(
+            vec!["BTC".into(), "ETH".into()],
+            vec!["111000000000".into(), "222000000000".into()],
+            vec![1000, 1500],
+            vec![555, 666],
+        );
+
+        context.block_timestamp = 2000;
+        testing_env!(context);
+
+        // no limit is configured and none is given
+        assert_eq!(
+            Some((111 * E9 * E9, 1000, 2000)),
+            contract.get_reference_data_fresh("BTC".into(), "USD".into(), None)
+        );
+        // BTC is 1000ns old
+        assert_eq!(
+            Some((111 * E9 * E9, 1000, 2000)),
+            contract.get_reference_data_fresh("BTC".into(), "USD".into(), Some(1000))
+        );
+        assert_eq!(
+            None,
+            contract.get_reference_data_fresh("BTC".into(), "USD".into(), Some(999))
+        );
+        // the quote leg is checked as well
+        assert_eq!(
+            None,
+            contract.get_reference_data_fresh("USD".into(), "BTC".into(), Some(999))
+        );
+        assert_eq!(
+            Some((E9 * E9 / 2, 1000, 1500)),
+            contract.get_reference_data_fresh("BTC".into(), "ETH".into(), Some(1000))
+        );
+    }
+
+    #[test]
+    fn test_get_reference_data_fresh_with_configured_max_age() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC", "ETH"]);
+        contract.add_relayer(bob());
+
+        contract.relay(
+            vec!["BTC".into(), "ETH".into()],
+            vec!["111000000000".into(), "222000000000".into()],
+            vec![1000, 1500],
+            vec![555, 666],
+        );
+        contract.set_default_max_age(600);
+        contract.set_max_age("BTC".into(), 1000);
+
+        context.storage_usage = env::storage_usage();
+        context.block_timestamp = 2000;
+        testing_env!(context);
+
+        assert_eq!(
+            Some((111 * E9 * E9, 1000, 2000)),
+            contract.get_reference_data_fresh("BTC".into(), "USD".into(), None)
+        );
+        // ETH is 500ns old, still within the default max age
+        assert_eq!(
+            Some((222 * E9 * E9, 1500, 2000)),
+            contract.get_reference_data_fresh("ETH".into(), "USD".into(), None)
+        );
+        // the stricter of the configured and the given max age applies
+        assert_eq!(
+            None,
+            contract.get_reference_data_fresh("ETH".into(), "USD".into(), Some(499))
+        );
+
+        // BTC is 1000ns old, which exceeds its own max age once lowered
+        contract.set_max_age("BTC".into(), 999);
+        assert_eq!(
+            None,
+            contract.get_reference_data_fresh("BTC".into(), "USD".into(), Some(5000))
+        );
+    }
+
+    #[test]
+    fn test_get_reference_data_with_large_rates() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BIG", "HUGE", "TINY"]);
+        contract.add_relayer(bob());
+
+        // br * E9 * E9 overflows u128 for both of these base rates
+        contract.relay(
+            vec!["BIG".into(), "HUGE".into(), "TINY".into()],
+            vec![
+                "340000000000000000000".into(),
+                u128::MAX.to_string(),
+                "1".into(),
+            ],
+            vec![333, 444, 555],
+            vec![1, 2, 3],
+        );
+
+        assert_eq!(
+            Some((340_000_000_000_000_000_000 * E9, 333, context.block_timestamp)),
+            contract.get_reference_data("BIG".into(), "USD".into())
+        );
+        assert_eq!(
+            Some((E9 * E9, 444, 444)),
+            contract.get_reference_data("HUGE".into(), "HUGE".into())
+        );
+        assert_eq!(
+            Some((E9 * E9 / 340_000_000_000_000_000_000, 555, 333)),
+            contract.get_reference_data("TINY".into(), "BIG".into())
+        );
+        assert_eq!(None, contract.get_reference_data("HUGE".into(), "TINY".into()));
+    }
+
+    #[test]
+    fn test_get_reference_data_with_zero_quote_rate() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["ZERO"]);
+        contract.add_relayer(bob());
+
+        contract.relay(vec!["ZERO".into()], vec!["0".into()], vec![333], vec![1]);
+
+        assert_eq!(
+            Some((0, 333, context.block_timestamp)),
+            contract.get_reference_data("ZERO".into(), "USD".into())
+        );
+        assert_eq!(None, contract.get_reference_data("USD".into(), "ZERO".into()));
+    }
+
+    #[test]
+    fn test_relay_and_get_v2() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        context.block_timestamp = 999;
+
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC", "ETH"]);
+        contract.add_relayer(bob());
+
+        contract.relay(
+            vec!["BTC".into(), "ETH".into()],
             vec!["111000000000".into(), "222000000000".into()],
             vec![333, 444],
             vec![555, 666],
         );
 
         assert_eq!(
-            Some(vec![
-                (111 * E9 * E9, 333, context.block_timestamp),
-                (222 * E9 * E9, 444, context.block_timestamp),
-                (E9 * E9 / 2, 333, 444),
-                (2 * E9 * E9, 444, 333),
-            ]),
-            contract.get_reference_data_bulk(
-                vec!["BTC".into(), "ETH".into(), "BTC".into(), "ETH".into()],
-                vec!["USD".into(), "USD".into(), "ETH".into(), "BTC".into()]
-            )
+            Some(RefData { rate: U128(111 * E9), resolve_time: U64(333), request_id: U64(555) }),
+            contract.get_refs_v2("BTC".into())
+        );
+        assert_eq!(None, contract.get_refs_v2("BAND".into()));
+        assert_eq!(
+            Some(ReferenceData {
+                rate: U128(E9 * E9 / 2),
+                last_updated_base: U64(333),
+                last_updated_quote: U64(444),
+                deprecated_base: false,
+                deprecated_quote: false,
+            }),
+            contract.get_reference_data_v2("BTC".into(), "ETH".into())
+        );
+        assert_eq!(None, contract.get_reference_data_v2("BAND".into(), "USD".into()));
+        assert_eq!(
+            Some(vec![
+                ReferenceData {
+                    rate: U128(222 * E9 * E9),
+                    last_updated_base: U64(444),
+                    last_updated_quote: U64(999),
+                    deprecated_base: false,
+                    deprecated_quote: false,
+                },
+                ReferenceData {
+                    rate: U128(2 * E9 * E9),
+                    last_updated_base: U64(444),
+                    last_updated_quote: U64(333),
+                    deprecated_base: false,
+                    deprecated_quote: false,
+                },
+            ]),
+            contract.get_reference_data_bulk_v2(
+                vec!["ETH".into(), "ETH".into()],
+                vec!["USD".into(), "BTC".into()]
+            )
+        );
+        assert_eq!(
+            None,
+            contract.get_reference_data_fresh_v2("BTC".into(), "ETH".into(), Some(600))
+        );
+    }
+
+    #[test]
+    fn test_reference_data_json_uses_strings() {
+        let data = ReferenceData::from((u128::MAX, 333, 444));
+        let json = serde_json::to_string(&data).unwrap();
+        assert_eq!(
+            format!(
+                concat!(
+                    r#"{{"rate":"{}","last_updated_base":"333","last_updated_quote":"444","#,
+                    r#""deprecated_base":false,"deprecated_quote":false}}"#,
+                ),
+                u128::MAX
+            ),
+            json
+        );
+        assert_eq!(data, serde_json::from_str::<ReferenceData>(&json).unwrap());
+
+        // data served before the deprecation flags parses as not deprecated
+        let json = r#"{"rate":"1","last_updated_base":"333","last_updated_quote":"444"}"#;
+        assert_eq!(
+            ReferenceData::from((1, 333, 444)),
+            serde_json::from_str::<ReferenceData>(json).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_implements_std_reference() {
+        fn assert_std_reference<T: StdReference>() {}
+        assert_std_reference::<StdReferenceBasic>();
+    }
+
+    #[test]
+    fn test_relay_and_get_history() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC"]);
+        contract.add_relayer(bob());
+        contract.set_history_depth(2);
+        assert_eq!(2, contract.get_history_depth());
+
+        for (i, block_timestamp) in [100, 200, 300].iter().enumerate() {
+            context.block_timestamp = *block_timestamp;
+            context.storage_usage = env::storage_usage();
+            testing_env!(context.clone());
+            contract.relay(
+                vec!["BTC".into()],
+                vec![format!("{}000000000", i + 1)],
+                vec![*block_timestamp - 10],
+                vec![i as u64],
+            );
+        }
+
+        let entry = |rate: u128, request_id: u64, relayed_at: u64, cumulative: u128| {
+            HistoryEntry {
+                rate: U128(rate),
+                resolve_time: U64(relayed_at - 10),
+                request_id: U64(request_id),
+                relayed_at: U64(relayed_at),
+                cumulative: U128(cumulative),
+            }
+        };
+        // the first relay fell off the ring
+        assert_eq!(
+            vec![entry(2 * E9, 1, 200, 100 * E9), entry(3 * E9, 2, 300, 300 * E9)],
+            contract.get_history("BTC".into(), 0, 10)
+        );
+        assert_eq!(
+            vec![entry(3 * E9, 2, 300, 300 * E9)],
+            contract.get_history("BTC".into(), 1, 10)
+        );
+        assert_eq!(
+            vec![entry(2 * E9, 1, 200, 100 * E9)],
+            contract.get_history("BTC".into(), 0, 1)
+        );
+        assert_eq!(Vec::<HistoryEntry>::new(), contract.get_history("ETH".into(), 0, 10));
+    }
+
+    #[test]
+    fn test_relay_and_get_reference_data_at() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC", "ETH"]);
+        contract.add_relayer(bob());
+
+        context.block_timestamp = 100;
+        context.storage_usage = env::storage_usage();
+        testing_env!(context.clone());
+        contract.relay(
+            vec!["BTC".into(), "ETH".into()],
+            vec!["100000000000".into(), "10000000000".into()],
+            vec![90, 95],
+            vec![1, 2],
+        );
+
+        context.block_timestamp = 200;
+        context.storage_usage = env::storage_usage();
+        testing_env!(context.clone());
+        contract.relay(vec!["BTC".into()], vec!["200000000000".into()], vec![190], vec![3]);
+
+        assert_eq!(None, contract.get_reference_data_at("BTC".into(), "USD".into(), U64(99)));
+        assert_eq!(
+            Some(ReferenceData::from((100 * E9 * E9, 90, 150))),
+            contract.get_reference_data_at("BTC".into(), "USD".into(), U64(150))
+        );
+        assert_eq!(
+            Some(ReferenceData::from((10 * E9 * E9, 90, 95))),
+            contract.get_reference_data_at("BTC".into(), "ETH".into(), U64(199))
+        );
+        assert_eq!(
+            Some(ReferenceData::from((20 * E9 * E9, 190, 95))),
+            contract.get_reference_data_at("BTC".into(), "ETH".into(), U64(200))
+        );
+        assert_eq!(
+            Some(ReferenceData::from((E9 * E9 / 20, 95, 190))),
+            contract.get_reference_data_at("ETH".into(), "BTC".into(), U64(1000))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_set_history_depth_fail_because_not_owner() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+
+        contract.set_history_depth(100);
+    }
+
+    #[test]
+    fn test_relay_and_get_twap_and_ema() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC", "ETH"]);
+        contract.add_relayer(bob());
+        contract.set_ema_period(100);
+        assert_eq!(100, contract.get_ema_period());
+
+        context.block_timestamp = 100;
+        context.storage_usage = env::storage_usage();
+        testing_env!(context.clone());
+        contract.relay(
+            vec!["BTC".into(), "ETH".into()],
+            vec!["10000000000".into(), "1000000000".into()],
+            vec![90, 95],
+            vec![1, 2],
+        );
+
+        context.block_timestamp = 200;
+        context.storage_usage = env::storage_usage();
+        testing_env!(context.clone());
+        contract.relay(vec!["BTC".into()], vec!["20000000000".into()], vec![190], vec![3]);
+
+        context.block_timestamp = 300;
+        testing_env!(context.clone());
+        assert_eq!(
+            Some(ReferenceData::from((15 * E9 * E9, 190, 300))),
+            contract.get_twap("BTC".into(), "USD".into(), 200)
+        );
+        assert_eq!(
+            Some(ReferenceData::from((20 * E9 * E9, 190, 300))),
+            contract.get_twap("BTC".into(), "USD".into(), 100)
+        );
+        assert_eq!(
+            Some(ReferenceData::from((15 * E9 * E9, 190, 95))),
+            contract.get_twap("BTC".into(), "ETH".into(), 200)
+        );
+        // the window reaches back before the first relay or before the genesis
+        assert_eq!(None, contract.get_twap("BTC".into(), "USD".into(), 250));
+        assert_eq!(None, contract.get_twap("BTC".into(), "USD".into(), 500));
+        assert_eq!(None, contract.get_twap("DOGE".into(), "USD".into(), 100));
+
+        // 10 until the second relay, then half way toward 20 after one period
+        assert_eq!(
+            Some(ReferenceData::from((15 * E9 * E9, 190, 300))),
+            contract.get_ema("BTC".into(), "USD".into())
+        );
+        assert_eq!(
+            Some(ReferenceData::from((15 * E9 * E9, 190, 95))),
+            contract.get_ema("BTC".into(), "ETH".into())
+        );
+        assert_eq!(None, contract.get_ema("DOGE".into(), "USD".into()));
+    }
+
+    #[test]
+    #[should_panic(expected = "ZERO_TWAP_WINDOW")]
+    fn test_get_twap_fail_because_zero_window() {
+        let context = get_context();
+        testing_env!(context);
+        let contract = StdReferenceBasic::new();
+
+        contract.get_twap("BTC".into(), "USD".into(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_set_ema_period_fail_because_not_owner() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+
+        contract.set_ema_period(100);
+    }
+
+    #[test]
+    fn test_non_usd_anchor() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        context.block_timestamp = 100;
+
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC"]);
+        assert_eq!(vec![("USD".to_string(), U128(E9))], contract.get_anchors());
+
+        contract.remove_anchor("USD".into());
+        contract.set_anchor("EUR".into(), U128(E9));
+        // a second anchor pegged at 1 USDT = 1.2 EUR
+        contract.set_anchor("USDT".into(), U128(1_200_000_000));
+        assert_eq!(
+            vec![("EUR".to_string(), U128(E9)), ("USDT".to_string(), U128(1_200_000_000))],
+            contract.get_anchors()
+        );
+
+        contract.add_relayer(bob());
+        contract.relay(vec!["BTC".into()], vec!["30000000000000".into()], vec![90], vec![1]);
+
+        assert_eq!(Some((E9, 100, 0)), contract.get_refs("EUR".into()));
+        assert_eq!(None, contract.get_refs("USD".into()));
+        assert_eq!(
+            Some((30_000 * E9 * E9, 90, 100)),
+            contract.get_reference_data("BTC".into(), "EUR".into())
+        );
+        assert_eq!(
+            Some((25_000 * E9 * E9, 90, 100)),
+            contract.get_reference_data("BTC".into(), "USDT".into())
+        );
+        assert_eq!(None, contract.get_reference_data("BTC".into(), "USD".into()));
+        assert_eq!(
+            Some(ReferenceData::from((30_000 * E9 * E9, 90, 100))),
+            contract.get_reference_data_at("BTC".into(), "EUR".into(), U64(100))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "CANNOT_RELAY_ANCHOR:EUR")]
+    fn test_relay_fail_because_anchor() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC"]);
+        contract.set_anchor("EUR".into(), U128(E9));
+        contract.add_relayer(bob());
+
+        contract.relay(
+            vec!["BTC".into(), "EUR".into()],
+            vec!["1".into(), "2".into()],
+            vec![1, 1],
+            vec![1, 1],
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "CANNOT_RELAY_ANCHOR:USD")]
+    fn test_force_relay_fail_because_anchor() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+
+        contract.force_relay(vec!["USD".into()], vec!["1".into()], vec![1], vec![1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_set_anchor_fail_because_not_owner() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+
+        contract.set_anchor("EUR".into(), U128(E9));
+    }
+
+    #[test]
+    #[should_panic(expected = "ZERO_ANCHOR_RATE")]
+    fn test_set_anchor_fail_because_zero_rate() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+
+        contract.set_anchor("EUR".into(), U128(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_ANCHOR")]
+    fn test_remove_anchor_fail_because_not_an_anchor() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+
+        contract.remove_anchor("EUR".into());
+    }
+
+    #[test]
+    fn test_register_and_list_symbols() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        assert_eq!(None, contract.get_symbol_info("BTC".into()));
+
+        contract.register_symbol("BTC".into(), U128(E9), "Bitcoin".into());
+        contract.register_symbol("ETH".into(), U128(E9), "Ether".into());
+        contract.register_symbol("NEAR".into(), U128(1_000_000), "NEAR".into());
+        contract.register_symbol("ETH".into(), U128(1_000), "Ethereum".into());
+
+        let info = |multiplier: u128, description: &str| SymbolInfo {
+            multiplier: U128(multiplier),
+            description: description.into(),
+            deprecated: false,
+        };
+        assert_eq!(Some(info(1_000, "Ethereum")), contract.get_symbol_info("ETH".into()));
+        assert_eq!(
+            vec![
+                ("BTC".to_string(), info(E9, "Bitcoin")),
+                ("ETH".to_string(), info(1_000, "Ethereum")),
+                ("NEAR".to_string(), info(1_000_000, "NEAR")),
+            ],
+            contract.list_symbols(0, 10)
+        );
+        assert_eq!(
+            vec![("ETH".to_string(), info(1_000, "Ethereum"))],
+            contract.list_symbols(1, 1)
+        );
+        assert_eq!(Vec::<(String, SymbolInfo)>::new(), contract.list_symbols(3, 10));
+        assert_eq!(Vec::<(String, SymbolInfo)>::new(), contract.list_symbols(u64::MAX, u64::MAX));
+    }
+
+    #[test]
+    fn test_relay_normalizes_rates() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        contract.add_relayer(bob());
+        contract.register_symbol("BTC".into(), U128(1_000_000), "Bitcoin".into());
+        contract.register_symbol("ETH".into(), U128(1_000_000_000_000), "Ether".into());
+
+        contract.relay(
+            vec!["BTC".into(), "ETH".into()],
+            vec!["50000000000".into(), "3000000000000000".into()],
+            vec![10, 20],
+            vec![1, 2],
+        );
+
+        assert_eq!(Some((50_000 * E9, 10, 1)), contract.get_refs("BTC".into()));
+        assert_eq!(Some((3_000 * E9, 20, 2)), contract.get_refs("ETH".into()));
+    }
+
+    #[test]
+    #[should_panic(expected = "UNREGISTERED_SYMBOL:DOGE")]
+    fn test_relay_fail_because_unregistered_symbol() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC"]);
+        contract.add_relayer(bob());
+
+        contract.relay(
+            vec!["BTC".into(), "DOGE".into()],
+            vec!["1".into(), "2".into()],
+            vec![1, 1],
+            vec![1, 1],
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "RATE_OVERFLOW_FOR:BTC")]
+    fn test_relay_fail_because_normalized_rate_overflows() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        contract.register_symbol("BTC".into(), U128(1), "Bitcoin".into());
+        contract.add_relayer(bob());
+
+        contract.relay(vec!["BTC".into()], vec![u128::MAX.to_string()], vec![1], vec![1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_register_symbol_fail_because_not_owner() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+
+        contract.register_symbol("BTC".into(), U128(E9), "Bitcoin".into());
+    }
+
+    #[test]
+    #[should_panic(expected = "ZERO_MULTIPLIER")]
+    fn test_register_symbol_fail_because_zero_multiplier() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+
+        contract.register_symbol("BTC".into(), U128(0), "Bitcoin".into());
+    }
+
+    #[test]
+    fn test_list_and_count_refs() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC", "ETH", "BAND"]);
+        contract.add_relayer(bob());
+        assert_eq!(0, contract.count_refs());
+        assert_eq!(Vec::<(String, RefData)>::new(), contract.list_refs(0, 10));
+
+        contract.relay(
+            vec!["ETH".into(), "BTC".into()],
+            vec!["2".into(), "1".into()],
+            vec![10, 10],
+            vec![1, 1],
+        );
+        contract.relay(
+            vec!["BAND".into(), "ETH".into()],
+            vec!["3".into(), "4".into()],
+            vec![20, 20],
+            vec![2, 2],
+        );
+
+        assert_eq!(3, contract.count_refs());
+        let refs = |symbol: &str, rate: u128, resolve_time: u64, request_id: u64| {
+            (symbol.to_string(), RefData::from((rate, resolve_time, request_id)))
+        };
+        // a relay over an existing symbol keeps its position
+        assert_eq!(
+            vec![refs("ETH", 4, 20, 2), refs("BTC", 1, 10, 1), refs("BAND", 3, 20, 2)],
+            contract.list_refs(0, 10)
+        );
+        assert_eq!(vec![refs("BTC", 1, 10, 1)], contract.list_refs(1, 1));
+        assert_eq!(Vec::<(String, RefData)>::new(), contract.list_refs(3, 10));
+    }
+
+    #[test]
+    fn test_deprecate_symbol() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC"]);
+        contract.add_relayer(bob());
+        contract.relay(vec!["BTC".into()], vec!["2000000000".into()], vec![10], vec![1]);
+
+        contract.deprecate_symbol("BTC".into());
+
+        assert!(contract.get_symbol_info("BTC".into()).unwrap().deprecated);
+        assert_eq!(
+            Some((2 * E9 * E9, 10, 0)),
+            contract.get_reference_data("BTC".into(), "USD".into())
+        );
+        // the v2 views serve the data flagged as deprecated
+        let flagged = ReferenceData {
+            deprecated_base: true,
+            ..ReferenceData::from((2 * E9 * E9, 10, 0))
+        };
+        assert_eq!(
+            Some(flagged.clone()),
+            contract.get_reference_data_v2("BTC".into(), "USD".into())
+        );
+        assert_eq!(Ok(flagged), contract.try_get_reference_data("BTC".into(), "USD".into()));
+        assert_eq!(
+            Ok(ReferenceData {
+                deprecated_quote: true,
+                ..ReferenceData::from((E9 * E9 / 2, 0, 10))
+            }),
+            contract.try_get_reference_data_fresh("USD".into(), "BTC".into(), None)
+        );
+        assert_eq!(
+            vec![Some(ReferenceData::from((E9 * E9, 0, 0))), None],
+            contract.get_reference_data_bulk_partial(
+                vec!["USD".into(), "ETH".into()],
+                vec!["USD".into(), "USD".into()]
+            )
+        );
+
+        // registering again reinstates the symbol
+        contract.register_symbol("BTC".into(), U128(E9), "Bitcoin".into());
+        assert!(!contract.get_symbol_info("BTC".into()).unwrap().deprecated);
+        contract.relay(vec!["BTC".into()], vec!["3000000000".into()], vec![20], vec![2]);
+        assert_eq!(Some((3 * E9, 20, 2)), contract.get_refs("BTC".into()));
+    }
+
+    #[test]
+    #[should_panic(expected = "DEPRECATED_SYMBOL:BTC")]
+    fn test_relay_fail_because_deprecated_symbol() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC"]);
+        contract.add_relayer(bob());
+        contract.deprecate_symbol("BTC".into());
+
+        contract.force_relay(vec!["BTC".into()], vec!["1".into()], vec![1], vec![1]);
+    }
+
+    #[test]
+    fn test_delete_symbol() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC", "ETH"]);
+        contract.add_relayer(bob());
+        contract.set_max_age("BTC".into(), 10);
+        contract.relay(
+            vec!["BTC".into(), "ETH".into()],
+            vec!["2000000000".into(), "3000000000".into()],
+            vec![10, 10],
+            vec![1, 1],
+        );
+        let storage_usage = env::storage_usage();
+
+        contract.delete_symbol("BTC".into());
+
+        assert!(env::storage_usage() < storage_usage);
+        assert_eq!(None, contract.get_symbol_info("BTC".into()));
+        assert_eq!(None, contract.get_refs("BTC".into()));
+        assert_eq!(None, contract.get_reference_data("BTC".into(), "USD".into()));
+        assert_eq!(0, contract.get_max_age("BTC".into()));
+        assert_eq!(Vec::<HistoryEntry>::new(), contract.get_history("BTC".into(), 0, 10));
+        assert!(contract.accumulators.get(&"BTC".to_string()).is_none());
+        assert_eq!(
+            vec![("ETH".to_string(), RefData::from((3 * E9, 10, 1)))],
+            contract.list_refs(0, 10)
+        );
+    }
+
+    #[test]
+    fn test_delete_symbol_keeps_refs_positions() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC", "ETH", "BAND", "NEAR"]);
+        contract.add_relayer(bob());
+        contract.relay(
+            vec!["BTC".into(), "ETH".into(), "BAND".into(), "NEAR".into()],
+            vec!["1".into(), "2".into(), "3".into(), "4".into()],
+            vec![10, 10, 10, 10],
+            vec![1, 1, 1, 1],
+        );
+
+        contract.delete_symbol("ETH".into());
+
+        // ETH leaves its position empty
+        let refs = |symbol: &str, rate: u128| (symbol.to_string(), RefData::from((rate, 10, 1)));
+        assert_eq!(4, contract.count_refs());
+        assert_eq!(vec![refs("BTC", 1)], contract.list_refs(0, 2));
+        assert_eq!(vec![refs("BAND", 3), refs("NEAR", 4)], contract.list_refs(2, 2));
+        let symbols: Vec<String> =
+            contract.list_symbols(0, 10).into_iter().map(|(symbol, _)| symbol).collect();
+        assert_eq!(vec!["BTC", "NEAR", "BAND"], symbols);
+
+        // and takes a new one when relayed again
+        register(&mut contract, &["ETH"]);
+        contract.relay(vec!["ETH".into()], vec!["2".into()], vec![10], vec![1]);
+        assert_eq!(5, contract.count_refs());
+        assert_eq!(vec![refs("NEAR", 4), refs("ETH", 2)], contract.list_refs(3, 2));
+    }
+
+    #[test]
+    #[should_panic(expected = "UNREGISTERED_SYMBOL:BTC")]
+    fn test_relay_fail_because_deleted_symbol() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC"]);
+        contract.add_relayer(bob());
+        contract.delete_symbol("BTC".into());
+
+        contract.relay(vec!["BTC".into()], vec!["1".into()], vec![1], vec![1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_deprecate_symbol_fail_because_not_owner() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC"]);
+
+        contract.deprecate_symbol("BTC".into());
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_delete_symbol_fail_because_not_owner() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC"]);
+
+        contract.delete_symbol("BTC".into());
+    }
+
+    #[test]
+    #[should_panic(expected = "UNREGISTERED_SYMBOL:BTC")]
+    fn test_delete_symbol_fail_because_unregistered() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+
+        contract.delete_symbol("BTC".into());
+    }
+
+    #[test]
+    fn test_try_get_reference_data() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        context.block_timestamp = 100;
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC", "ZERO"]);
+        contract.add_relayer(bob());
+        contract.relay(
+            vec!["BTC".into(), "ZERO".into()],
+            vec!["2000000000".into(), "0".into()],
+            vec![10, 10],
+            vec![1, 1],
+        );
+
+        assert_eq!(
+            Ok(ReferenceData::from((2 * E9 * E9, 10, 100))),
+            contract.try_get_reference_data("BTC".into(), "USD".into())
+        );
+        assert_eq!(
+            Err(Error::RefDataNotAvailableFor { symbol: "ETH".into() }),
+            contract.try_get_reference_data("BTC".into(), "ETH".into())
+        );
+        assert_eq!(
+            Err(Error::ZeroRateFor { symbol: "ZERO".into() }),
+            contract.try_get_reference_data("BTC".into(), "ZERO".into())
+        );
+        assert_eq!(
+            Err(Error::RefDataTooOldFor { symbol: "BTC".into() }),
+            contract.try_get_reference_data_fresh("BTC".into(), "USD".into(), Some(50))
+        );
+        assert_eq!(
+            r#"{"Err":{"code":"REF_DATA_NOT_AVAILABLE_FOR","symbol":"ETH"}}"#,
+            serde_json::to_string(&contract.try_get_reference_data("ETH".into(), "USD".into()))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "FAIL_TO_PARSE_RATE:BTC:1.5")]
+    fn test_relay_fail_because_unparsable_rate() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC"]);
+        contract.add_relayer(bob());
+
+        contract.relay(vec!["BTC".into()], vec!["1.5".into()], vec![1], vec![1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "BAD_RATES_LENGTH")]
+    fn test_relay_fail_because_bad_rates_length() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        contract.add_relayer(bob());
+
+        contract.relay(vec!["BTC".into()], vec![], vec![1], vec![1]);
+    }
+
+    #[test]
+    fn test_get_reference_data_bulk_partial() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        context.block_timestamp = 100;
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC", "ETH"]);
+        contract.add_relayer(bob());
+        contract.relay(
+            vec!["BTC".into(), "ETH".into()],
+            vec!["20000000000".into(), "2000000000".into()],
+            vec![10, 20],
+            vec![1, 2],
+        );
+
+        assert_eq!(
+            None,
+            contract.get_reference_data_bulk_v2(
+                vec!["BTC".into(), "DOGE".into(), "BTC".into()],
+                vec!["USD".into(), "USD".into(), "ETH".into()]
+            )
+        );
+        assert_eq!(
+            vec![
+                Some(ReferenceData::from((20 * E9 * E9, 10, 100))),
+                None,
+                Some(ReferenceData::from((10 * E9 * E9, 10, 20))),
+            ],
+            contract.get_reference_data_bulk_partial(
+                vec!["BTC".into(), "DOGE".into(), "BTC".into()],
+                vec!["USD".into(), "USD".into(), "ETH".into()]
+            )
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "BAD_INPUT_LENGTH")]
+    fn test_get_reference_data_bulk_partial_fail_because_bad_input_length() {
+        let context = get_context();
+        testing_env!(context);
+        let contract = StdReferenceBasic::new();
+
+        contract.get_reference_data_bulk_partial(vec!["BTC".into()], vec![]);
+    }
+
+    #[test]
+    fn test_relay_emits_events() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        contract.add_relayer(bob());
+        contract.register_symbol("BTC".into(), U128(1_000), "Bitcoin".into());
+        register(&mut contract, &["ETH"]);
+        assert_eq!(
+            vec![
+                json!({
+                    "standard": "std_reference_basic",
+                    "version": "1.0.0",
+                    "event": "add_relayer",
+                    "data": [{"relayer": "bob.near"}],
+                }),
+                json!({
+                    "standard": "std_reference_basic",
+                    "version": "1.0.0",
+                    "event": "register_symbol",
+                    "data": [{"symbol": "BTC", "multiplier": "1000", "description": "Bitcoin"}],
+                }),
+            ],
+            emitted_events()
+        );
+
+        contract.relay(
+            vec!["BTC".into(), "ETH".into()],
+            vec!["20000".into(), "3000000000".into()],
+            vec![10, 20],
+            vec![1, 2],
+        );
+        contract.relay(
+            vec!["BTC".into(), "ETH".into()],
+            vec!["30000".into(), "4000000000".into()],
+            vec![30, 20],
+            vec![3, 4],
+        );
+
+        let event = |event: &str, data: Value| {
+            json!({
+                "standard": "std_reference_basic",
+                "version": "1.0.0",
+                "event": event,
+                "data": data,
+            })
+        };
+        let relayed = |symbol: &str, rate: &str, resolve_time: &str, request_id: &str| {
+            json!({
+                "symbol": symbol,
+                "rate": rate,
+                "resolve_time": resolve_time,
+                "request_id": request_id,
+            })
+        };
+        // rates are reported normalized, as stored
+        assert_eq!(
+            vec![
+                event(
+                    "relay",
+                    json!([
+                        relayed("BTC", "20000000000", "10", "1"),
+                        relayed("ETH", "3000000000", "20", "2"),
+                    ])
+                ),
+                event("relay", json!([relayed("BTC", "30000000000", "30", "3")])),
+                event(
+                    "relay_skip",
+                    json!([{"symbol": "ETH", "resolve_time": "20", "request_id": "4"}])
+                ),
+            ],
+            emitted_events()
+        );
+    }
+
+    #[test]
+    fn test_add_and_remove_feeder() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+
+        contract.add_feeder(carol());
+        contract.add_feeder(alice());
+        assert!(contract.is_feeder(carol()));
+        assert!(!contract.is_relayer(carol()));
+        assert_eq!(vec![carol(), alice()], contract.get_feeders());
+
+        contract.remove_feeder(carol());
+        assert!(!contract.is_feeder(carol()));
+        assert_eq!(vec![alice()], contract.get_feeders());
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_add_feeder_fail_because_not_owner() {
+        let mut context = get_context();
+        context.predecessor_account_id = carol();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+
+        contract.add_feeder(carol());
+    }
+
+    #[test]
+    fn test_set_quorum() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+
+        assert_eq!(1, contract.get_quorum());
+        contract.set_quorum(3);
+        assert_eq!(3, contract.get_quorum());
+    }
+
+    #[test]
+    #[should_panic(expected = "ZERO_QUORUM")]
+    fn test_set_quorum_fail_because_zero() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+
+        contract.set_quorum(0);
+    }
+
+    #[test]
+    fn test_submit_finalizes_median() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+        contract.register_symbol("BTC".into(), U128(1_000), "Bitcoin".into());
+        register(&mut contract, &["ETH"]);
+        contract.add_feeder(alice());
+        contract.add_feeder(bob());
+        contract.add_feeder(carol());
+        contract.set_quorum(3);
+        take_emitted();
+
+        let submissions = [
+            (alice(), "30000", 110),
+            (bob(), "20000", 100),
+            (carol(), "60000", 120),
+        ];
+        for (i, (feeder, rate, resolve_time)) in submissions.iter().enumerate() {
+            assert_eq!(None, contract.get_refs("BTC".into()));
+            context.predecessor_account_id = feeder.clone();
+            context.block_timestamp = 1_000 + i as u64;
+            context.storage_usage = env::storage_usage();
+            testing_env!(context.clone());
+            contract.submit(vec!["BTC".into()], vec![rate.to_string()], vec![*resolve_time]);
+        }
+
+        // the median rate, normalized, with the median resolve time and the round id
+        assert_eq!(Some((30_000_000_000, 110, 1)), contract.get_refs("BTC".into()));
+        assert_eq!(Some(1), contract.get_finalized_round("BTC".into()));
+        assert_eq!(None, contract.get_finalized_round("ETH".into()));
+        let round = contract.get_round_submissions("BTC".into(), 1);
+        assert_eq!(3, round.len());
+        assert_eq!(
+            Submission {
+                feeder: bob(),
+                rate: U128(20_000_000_000),
+                resolve_time: U64(100),
+                submitted_at: U64(1_001),
+            },
+            round[1]
+        );
+        assert_eq!(Vec::<Submission>::new(), contract.get_round_submissions("BTC".into(), 2));
+
+        let events = emitted_events();
+        assert_eq!(
+            vec!["submit", "submit", "submit", "finalize_round", "relay"],
+            events.iter().map(|event| event["event"].as_str().unwrap()).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            json!([{"symbol": "BTC", "round_id": "1", "submissions": 3}]),
+            events[3]["data"]
+        );
+        assert_eq!(
+            json!([{
+                "symbol": "BTC",
+                "rate": "30000000000",
+                "resolve_time": "110",
+                "request_id": "1",
+            }]),
+            events[4]["data"]
+        );
+
+        // a finalized round that is not newer than the stored reference is not stored
+        context.predecessor_account_id = bob();
+        testing_env!(context.clone());
+        contract.set_quorum(1);
+        contract.add_relayer(bob());
+        contract.relay(vec!["BTC".into()], vec!["50000".into()], vec![200], vec![7]);
+        contract.submit(vec!["BTC".into()], vec!["40000".into()], vec![150]);
+
+        assert_eq!(Some(2), contract.get_finalized_round("BTC".into()));
+        assert_eq!(Some((50_000_000_000, 200, 7)), contract.get_refs("BTC".into()));
+    }
+
+    #[test]
+    fn test_submit_ignores_removed_feeders() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC"]);
+        contract.add_feeder(alice());
+        contract.add_feeder(carol());
+        contract.set_quorum(2);
+
+        context.predecessor_account_id = alice();
+        testing_env!(context.clone());
+        contract.submit(vec!["BTC".into()], vec!["1".into()], vec![10]);
+
+        context.predecessor_account_id = bob();
+        context.storage_usage = env::storage_usage();
+        testing_env!(context.clone());
+        contract.remove_feeder(alice());
+        contract.add_feeder(bob());
+
+        // alice's submission no longer counts, so carol's alone does not finalize the round
+        context.predecessor_account_id = carol();
+        context.storage_usage = env::storage_usage();
+        testing_env!(context.clone());
+        contract.submit(vec!["BTC".into()], vec!["2".into()], vec![10]);
+        assert_eq!(None, contract.get_finalized_round("BTC".into()));
+
+        context.predecessor_account_id = bob();
+        context.storage_usage = env::storage_usage();
+        testing_env!(context);
+        contract.submit(vec!["BTC".into()], vec!["4".into()], vec![10]);
+        assert_eq!(Some(1), contract.get_finalized_round("BTC".into()));
+        assert_eq!(Some((3, 10, 1)), contract.get_refs("BTC".into()));
+    }
+
+    #[test]
+    fn test_submit_drops_expired_submissions() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC"]);
+        contract.add_feeder(alice());
+        contract.add_feeder(carol());
+        contract.set_quorum(2);
+        assert_eq!(DEFAULT_ROUND_TIMEOUT, contract.get_round_timeout());
+        contract.set_round_timeout(100);
+        assert_eq!(100, contract.get_round_timeout());
+
+        context.predecessor_account_id = alice();
+        context.block_timestamp = 1_000;
+        context.storage_usage = env::storage_usage();
+        testing_env!(context.clone());
+        contract.submit(vec!["BTC".into()], vec!["1".into()], vec![10]);
+
+        // alice's submission expired, so the round stays open
+        context.predecessor_account_id = carol();
+        context.block_timestamp = 1_101;
+        context.storage_usage = env::storage_usage();
+        testing_env!(context.clone());
+        contract.submit(vec!["BTC".into()], vec!["2".into()], vec![10]);
+        assert_eq!(None, contract.get_finalized_round("BTC".into()));
+        assert_eq!(1, contract.get_round_submissions("BTC".into(), 1).len());
+
+        context.predecessor_account_id = alice();
+        context.block_timestamp = 1_150;
+        context.storage_usage = env::storage_usage();
+        testing_env!(context);
+        contract.submit(vec!["BTC".into()], vec!["4".into()], vec![10]);
+        assert_eq!(Some((3, 10, 1)), contract.get_refs("BTC".into()));
+    }
+
+    #[test]
+    fn test_submit_resolve_time_resists_a_stale_feeder() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC"]);
+        contract.add_relayer(bob());
+        contract.add_feeder(alice());
+        contract.add_feeder(bob());
+        contract.add_feeder(carol());
+        contract.set_quorum(3);
+        contract.relay(vec!["BTC".into()], vec!["1".into()], vec![100], vec![7]);
+
+        // one feeder lagging behind the stored reference does not get the round skipped
+        for (feeder, resolve_time) in [(alice(), 50), (bob(), 200), (carol(), 210)] {
+            context.predecessor_account_id = feeder;
+            context.storage_usage = env::storage_usage();
+            testing_env!(context.clone());
+            contract.submit(vec!["BTC".into()], vec!["2".into()], vec![resolve_time]);
+        }
+
+        assert_eq!(Some((2, 200, 1)), contract.get_refs("BTC".into()));
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_set_round_timeout_fail_because_not_owner() {
+        let context = get_context();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+
+        contract.set_round_timeout(100);
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_A_FEEDER")]
+    fn test_submit_fail_because_not_feeder() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC"]);
+        contract.add_relayer(bob());
+
+        contract.submit(vec!["BTC".into()], vec!["1".into()], vec![1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "UNREGISTERED_SYMBOL:ETH")]
+    fn test_submit_fail_because_unregistered_symbol() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC"]);
+        contract.add_feeder(bob());
+
+        contract.submit(vec!["BTC".into(), "ETH".into()], vec!["1".into(), "2".into()], vec![1, 1]);
+    }
+
+    // Bob owns the contract and relays BTC at 60000, with a 10% circuit breaker.
+    fn contract_with_breaker() -> StdReferenceBasic {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC"]);
+        contract.add_relayer(bob());
+        contract.add_relayer(carol());
+        contract.relay(vec!["BTC".into()], vec!["60000".into()], vec![10], vec![1]);
+        contract.set_max_deviation("BTC".into(), 1_000);
+        take_emitted();
+        contract
+    }
+
+    fn relay_btc(contract: &mut StdReferenceBasic, rate: &str, resolve_time: u64) {
+        let resolve_times = vec![resolve_time];
+        contract.relay(vec!["BTC".into()], vec![rate.into()], resolve_times.clone(), resolve_times);
+    }
+
+    fn relay_btc_as(
+        contract: &mut StdReferenceBasic,
+        relayer: AccountId,
+        rate: &str,
+        resolve_time: u64,
+    ) {
+        let mut context = get_context();
+        context.predecessor_account_id = relayer;
+        context.storage_usage = env::storage_usage();
+        testing_env!(context);
+        relay_btc(contract, rate, resolve_time);
+    }
+
+    #[test]
+    fn test_set_max_deviation() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        register(&mut contract, &["BTC"]);
+
+        assert_eq!(None, contract.get_max_deviation("BTC".into()));
+        contract.set_max_deviation("BTC".into(), 500);
+        assert_eq!(Some(500), contract.get_max_deviation("BTC".into()));
+        contract.remove_max_deviation("BTC".into());
+        assert_eq!(None, contract.get_max_deviation("BTC".into()));
+    }
+
+    #[test]
+    #[should_panic(expected = "UNREGISTERED_SYMBOL:BTC")]
+    fn test_set_max_deviation_fail_because_unregistered() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+
+        contract.set_max_deviation("BTC".into(), 500);
+    }
+
+    #[test]
+    #[should_panic(expected = "MAX_DEVIATION_NOT_SET:BTC")]
+    fn test_remove_max_deviation_fail_because_not_set() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+
+        contract.remove_max_deviation("BTC".into());
+    }
+
+    #[test]
+    fn test_breaker_holds_back_large_jump_until_corroborated() {
+        let mut contract = contract_with_breaker();
+
+        // within 10% is stored as usual
+        relay_btc(&mut contract, "66000", 20);
+        assert_eq!(Some((66000, 20, 20)), contract.get_refs("BTC".into()));
+
+        relay_btc(&mut contract, "6", 30);
+        assert_eq!(Some((66000, 20, 20)), contract.get_refs("BTC".into()));
+        let pending = PendingUpdate {
+            rate: U128(6),
+            resolve_time: U64(30),
+            request_id: U64(30),
+            previous_rate: U128(66000),
+            relayed_at: U64(0),
+            relayer: bob(),
+        };
+        assert_eq!(Some(pending.clone()), contract.get_pending_update("BTC".into()));
+        assert_eq!(vec![("BTC".to_string(), pending)], contract.list_pending_updates(0, 10));
+        assert_eq!(
+            vec![
+                json!({
+                    "standard": "std_reference_basic",
+                    "version": "1.0.0",
+                    "event": "relay",
+                    "data": [{
+                        "symbol": "BTC",
+                        "rate": "66000",
+                        "resolve_time": "20",
+                        "request_id": "20",
+                    }],
+                }),
+                json!({
+                    "standard": "std_reference_basic",
+                    "version": "1.0.0",
+                    "event": "breaker_trip",
+                    "data": [{
+                        "symbol": "BTC",
+                        "rate": "6",
+                        "previous_rate": "66000",
+                        "resolve_time": "30",
+                        "request_id": "30",
+                    }],
+                }),
+            ],
+            emitted_events()
+        );
+
+        // a second jump away from the pending rate replaces it
+        relay_btc(&mut contract, "90", 40);
+        assert_eq!(Some(U128(90)), contract.get_pending_update("BTC".into()).map(|p| p.rate));
+
+        // one within 10% of the pending rate by another relayer corroborates it
+        relay_btc_as(&mut contract, carol(), "95", 50);
+        assert_eq!(Some((95, 50, 50)), contract.get_refs("BTC".into()));
+        assert_eq!(None, contract.get_pending_update("BTC".into()));
+    }
+
+    #[test]
+    fn test_breaker_not_corroborated_by_same_relayer() {
+        let mut contract = contract_with_breaker();
+
+        relay_btc(&mut contract, "90", 20);
+        relay_btc(&mut contract, "95", 30);
+
+        assert_eq!(Some((60000, 10, 1)), contract.get_refs("BTC".into()));
+        let pending = contract.get_pending_update("BTC".into()).unwrap();
+        assert_eq!((U128(95), bob()), (pending.rate, pending.relayer));
+    }
+
+    #[test]
+    fn test_breaker_not_corroborated_by_removed_relayer() {
+        let mut contract = contract_with_breaker();
+        relay_btc(&mut contract, "90", 20);
+        contract.remove_relayer(carol());
+
+        // carol may still finalize rounds as a feeder, but not corroborate as a relayer
+        contract.add_feeder(carol());
+        let mut context = get_context();
+        context.predecessor_account_id = carol();
+        context.storage_usage = env::storage_usage();
+        testing_env!(context);
+        contract.submit(vec!["BTC".into()], vec!["95".into()], vec![30]);
+
+        assert_eq!(Some((60000, 10, 1)), contract.get_refs("BTC".into()));
+        assert_eq!(Some(U128(95)), contract.get_pending_update("BTC".into()).map(|p| p.rate));
+    }
+
+    #[test]
+    fn test_breaker_pending_update_cleared_by_regular_relay() {
+        let mut contract = contract_with_breaker();
+
+        relay_btc(&mut contract, "6", 20);
+        relay_btc(&mut contract, "61000", 30);
+
+        assert_eq!(Some((61000, 30, 30)), contract.get_refs("BTC".into()));
+        assert_eq!(None, contract.get_pending_update("BTC".into()));
+        assert_eq!(0, contract.list_pending_updates(0, 10).len());
+    }
+
+    #[test]
+    fn test_force_relay_bypasses_breaker() {
+        let mut contract = contract_with_breaker();
+
+        relay_btc(&mut contract, "6", 20);
+        contract.force_relay(vec!["BTC".into()], vec!["7".into()], vec![15], vec![15]);
+
+        assert_eq!(Some((7, 15, 15)), contract.get_refs("BTC".into()));
+        assert_eq!(None, contract.get_pending_update("BTC".into()));
+    }
+
+    #[test]
+    fn test_confirm_and_reject_pending_update() {
+        let mut contract = contract_with_breaker();
+        contract.set_guardian(Some(carol()));
+        assert_eq!(Some(carol()), contract.get_guardian());
+        relay_btc(&mut contract, "6", 20);
+
+        let mut context = get_context();
+        context.predecessor_account_id = carol();
+        context.storage_usage = env::storage_usage();
+        testing_env!(context.clone());
+        contract.reject_pending_update("BTC".into());
+        assert_eq!(Some((60000, 10, 1)), contract.get_refs("BTC".into()));
+        assert_eq!(None, contract.get_pending_update("BTC".into()));
+
+        context.predecessor_account_id = bob();
+        testing_env!(context.clone());
+        relay_btc(&mut contract, "6", 30);
+        context.predecessor_account_id = carol();
+        context.block_timestamp = 100;
+        testing_env!(context);
+        contract.confirm_pending_update("BTC".into());
+        assert_eq!(Some((6, 30, 30)), contract.get_refs("BTC".into()));
+        assert_eq!(None, contract.get_pending_update("BTC".into()));
+        assert_eq!(2, contract.get_history("BTC".into(), 0, 10).len());
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_A_GUARDIAN")]
+    fn test_confirm_pending_update_fail_because_not_guardian() {
+        let mut contract = contract_with_breaker();
+        relay_btc(&mut contract, "6", 20);
+
+        let mut context = get_context();
+        context.predecessor_account_id = carol();
+        context.storage_usage = env::storage_usage();
+        testing_env!(context);
+        contract.confirm_pending_update("BTC".into());
+    }
+
+    #[test]
+    #[should_panic(expected = "NO_PENDING_UPDATE_FOR:BTC")]
+    fn test_confirm_pending_update_fail_because_nothing_pending() {
+        let mut contract = contract_with_breaker();
+
+        contract.confirm_pending_update("BTC".into());
+    }
+
+    // Pausing itself is tested in `std_ref_interface::admin`.
+    #[test]
+    fn test_force_relay_while_paused() {
+        let mut contract = contract_with_breaker();
+        contract.pause();
+        assert_eq!(
+            Err(Error::Paused),
+            contract.try_get_reference_data("BTC".into(), "USD".into())
+        );
+
+        // the owner can still correct the stored data
+        contract.force_relay(vec!["BTC".into()], vec!["61000".into()], vec![20], vec![2]);
+        contract.unpause();
+        assert_eq!(Some((61000, 20, 2)), contract.get_refs("BTC".into()));
+    }
+
+    #[test]
+    #[should_panic(expected = "PAUSED")]
+    fn test_relay_fail_because_paused() {
+        let mut contract = contract_with_breaker();
+        contract.pause();
+
+        relay_btc(&mut contract, "61000", 20);
+    }
+
+    #[test]
+    #[should_panic(expected = "PAUSED")]
+    fn test_count_refs_fail_because_paused() {
+        let mut contract = contract_with_breaker();
+        contract.pause();
+
+        contract.count_refs();
+    }
+
+    #[test]
+    fn test_migrate_from_unversioned_state() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        context.block_timestamp = 100;
+        testing_env!(context);
+        let mut old_refs = UnorderedMap::new(b"refs".to_vec());
+        old_refs.insert(&"BTC".to_string(), &(2 * E9, 10, 1));
+        env::state_write(&OldStdReferenceBasic { refs: old_refs, owner: bob() });
+
+        let contract = StdReferenceBasic::migrate();
+
+        assert_eq!(bob(), contract.get_owner());
+        assert_eq!(vec![bob()], contract.get_relayers());
+        assert_eq!(vec![("USD".to_string(), U128(E9))], contract.get_anchors());
+        assert_eq!(
+            Some(SymbolInfo { multiplier: U128(E9), description: "".into(), deprecated: false }),
+            contract.get_symbol_info("BTC".into())
+        );
+        assert_eq!(
+            Some((2 * E9 * E9, 10, 100)),
+            contract.get_reference_data("BTC".into(), "USD".into())
+        );
+        // the averages start from the stored rate
+        assert_eq!(
+            Some(ReferenceData::from((2 * E9 * E9, 10, 100))),
+            contract.get_ema("BTC".into(), "USD".into())
+        );
+        assert_eq!(STATE_VERSION, contract.get_state_version());
+        assert_eq!(
+            vec![json!({
+                "standard": "std_reference_basic",
+                "version": "1.0.0",
+                "event": "migrate",
+                "data": [{"from_version": 0, "to_version": 3}],
+            })],
+            emitted_events()
+        );
+    }
+
+    #[test]
+    fn test_relay_as_old_owner_after_migrating_from_unversioned_state() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut old_refs = UnorderedMap::new(b"refs".to_vec());
+        old_refs.insert(&"BTC".to_string(), &(2 * E9, 10, 1));
+        env::state_write(&OldStdReferenceBasic { refs: old_refs, owner: bob() });
+        let mut contract = StdReferenceBasic::migrate();
+
+        contract.relay(vec!["BTC".into()], vec!["3000000000".into()], vec![20], vec![2]);
+
+        assert_eq!(Some((3 * E9, 20, 2)), contract.get_refs("BTC".into()));
+    }
+
+    #[test]
+    #[should_panic(expected = "UNSUPPORTED_STATE_VERSION:4")]
+    fn test_migrate_fail_because_unsupported_version() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        env::state_write(&StdReferenceBasic::new());
+        upgrade::set_state_version(4);
+
+        StdReferenceBasic::migrate();
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_migrate_fail_because_not_owner() {
+        let mut context = get_context();
+        context.predecessor_account_id = carol();
+        testing_env!(context);
+        let refs = UnorderedMap::new(b"refs".to_vec());
+        env::state_write(&OldStdReferenceBasic { refs, owner: bob() });
+
+        StdReferenceBasic::migrate();
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_upgrade_fail_because_not_owner() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context.clone());
+        let contract = StdReferenceBasic::new();
+
+        context.predecessor_account_id = carol();
+        testing_env!(context);
+        contract.upgrade(Base64VecU8(b"code".to_vec()));
+    }
+
+    #[test]
+    fn test_migrate_from_v1() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        contract.add_relayer(bob());
+        register(&mut contract, &["BTC"]);
+        contract.relay(vec!["BTC".into()], vec!["100".into()], vec![10], vec![1]);
+        let StdReferenceBasic {
+            refs,
+            owner,
+            relayers,
+            default_max_age,
+            max_ages,
+            history,
+            accumulators,
+            ema_period,
+            anchors,
+            symbols,
+            pending_owner,
+            feeders,
+            rounds,
+            guardian,
+            max_deviations,
+            pending_updates,
+            paused,
+            bridge: _,
+            mut relay_order,
+        } = contract;
+        relay_order.clear();
+        env::state_write(&StdReferenceBasicV1 {
+            refs,
+            owner,
+            relayers,
+            default_max_age,
+            max_ages,
+            history,
+            accumulators,
+            ema_period,
+            anchors,
+            symbols,
+            pending_owner,
+            feeders,
+            rounds,
+            guardian,
+            max_deviations,
+            pending_updates,
+            paused,
+        });
+        upgrade::set_state_version(1);
+        take_emitted();
+
+        let contract = StdReferenceBasic::migrate();
+
+        assert_eq!(Some((100, 10, 1)), contract.get_refs("BTC".into()));
+        assert_eq!(vec![bob()], contract.get_relayers());
+        assert_eq!(1, contract.get_history("BTC".into(), 0, 10).len());
+        assert_eq!(None, contract.get_bridge_config());
+        assert_eq!(U64(0), contract.get_total_validator_power());
+        assert_eq!(STATE_VERSION, contract.get_state_version());
+        assert_eq!(
+            vec![json!({
+                "standard": "std_reference_basic",
+                "version": "1.0.0",
+                "event": "migrate",
+                "data": [{"from_version": 1, "to_version": 3}],
+            })],
+            emitted_events()
+        );
+    }
+
+    #[test]
+    fn test_migrate_from_v2() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        contract.add_relayer(bob());
+        register(&mut contract, &["BTC", "ETH"]);
+        contract.relay(
+            vec!["BTC".into(), "ETH".into()],
+            vec!["100".into(), "200".into()],
+            vec![10, 10],
+            vec![1, 1],
+        );
+        let StdReferenceBasic {
+            refs,
+            owner,
+            relayers,
+            default_max_age,
+            max_ages,
+            history,
+            accumulators,
+            ema_period,
+            anchors,
+            symbols,
+            pending_owner,
+            feeders,
+            rounds,
+            guardian,
+            max_deviations,
+            pending_updates,
+            paused,
+            bridge,
+            mut relay_order,
+        } = contract;
+        relay_order.clear();
+        env::state_write(&StdReferenceBasicV2 {
+            refs,
+            owner,
+            relayers,
+            default_max_age,
+            max_ages,
+            history,
+            accumulators,
+            ema_period,
+            anchors,
+            symbols,
+            pending_owner,
+            feeders,
+            rounds,
+            guardian,
+            max_deviations,
+            pending_updates,
+            paused,
+            bridge,
+        });
+        upgrade::set_state_version(2);
+        take_emitted();
+
+        let mut contract = StdReferenceBasic::migrate();
+
+        // the references keep the order they were listed in
+        let refs = |symbol: &str, rate: u128| (symbol.to_string(), RefData::from((rate, 10, 1)));
+        assert_eq!(2, contract.count_refs());
+        assert_eq!(vec![refs("BTC", 100), refs("ETH", 200)], contract.list_refs(0, 10));
+        contract.delete_symbol("BTC".into());
+        assert_eq!(vec![refs("ETH", 200)], contract.list_refs(1, 1));
+        assert_eq!(STATE_VERSION, contract.get_state_version());
+    }
+
+    #[test]
+    fn test_update_validator_powers() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        let validators: Vec<(Base64VecU8, U64)> = fixture("validators");
+        let (first, second) = (validators[0].0.clone(), validators[1].0.clone());
+
+        contract.update_validator_powers(vec![(first.clone(), U64(10)), (second.clone(), U64(20))]);
+        assert_eq!(U64(30), contract.get_total_validator_power());
+        contract.update_validator_powers(vec![(first.clone(), U64(5)), (second.clone(), U64(0))]);
+        assert_eq!(U64(5), contract.get_total_validator_power());
+        assert_eq!(vec![(first, U64(5))], contract.get_validators());
+        assert_eq!(
+            vec![
+                json!({
+                    "standard": "std_reference_basic",
+                    "version": "1.0.0",
+                    "event": "update_validator_powers",
+                    "data": [
+                        {"pub_key": validators[0].0, "power": "10"},
+                        {"pub_key": validators[1].0, "power": "20"},
+                    ],
+                }),
+                json!({
+                    "standard": "std_reference_basic",
+                    "version": "1.0.0",
+                    "event": "update_validator_powers",
+                    "data": [
+                        {"pub_key": validators[0].0, "power": "5"},
+                        {"pub_key": validators[1].0, "power": "0"},
+                    ],
+                }),
+            ],
+            emitted_events()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "INVALID_VALIDATOR_PUB_KEY")]
+    fn test_update_validator_powers_fail_because_invalid_pub_key() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+
+        contract.update_validator_powers(vec![(Base64VecU8(vec![2; 32]), U64(10))]);
+    }
+
+    #[test]
+    #[should_panic(expected = "VALIDATOR_POWER_OVERFLOW")]
+    fn test_update_validator_powers_fail_because_total_power_overflows() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        let validators: Vec<(Base64VecU8, U64)> = fixture("validators");
+
+        contract.update_validator_powers(vec![
+            (validators[0].0.clone(), U64(u64::MAX)),
+            (validators[1].0.clone(), U64(1)),
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_set_bridge_config_fail_because_not_owner() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+
+        context.predecessor_account_id = carol();
+        testing_env!(context);
+        let config = BridgeConfig { chain_id: "band".into(), oracle_script_id: U64(3) };
+        contract.set_bridge_config(config);
+    }
+
+    #[test]
+    fn test_relay_with_proof() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+        set_up_bridge(&mut contract);
+
+        // anyone may relay a proof
+        context.predecessor_account_id = carol();
+        context.storage_usage = env::storage_usage();
+        testing_env!(context);
+        contract.relay_with_proof(fixture("btc_eth"));
+
+        // rates were requested with a multiplier of 1e6
+        let resolve_time = 1_650_000_010_000_000_000;
+        let btc = Some((20_000_123_456_000, resolve_time, 1001));
+        assert_eq!(btc, contract.get_refs("BTC".into()));
+        assert_eq!(Some((1_500 * E9, resolve_time, 1001)), contract.get_refs("ETH".into()));
+        assert_eq!(
+            vec![
+                json!({
+                    "standard": "std_reference_basic",
+                    "version": "1.0.0",
+                    "event": "relay_with_proof",
+                    "data": [
+                        {"relayer": "carol.near", "block_height": "5001", "request_id": "1001"},
+                    ],
+                }),
+                json!({
+                    "standard": "std_reference_basic",
+                    "version": "1.0.0",
+                    "event": "relay",
+                    "data": [
+                        {
+                            "symbol": "BTC",
+                            "rate": "20000123456000",
+                            "resolve_time": "1650000010000000000",
+                            "request_id": "1001",
+                        },
+                        {
+                            "symbol": "ETH",
+                            "rate": "1500000000000",
+                            "resolve_time": "1650000010000000000",
+                            "request_id": "1001",
+                        },
+                    ],
+                }),
+            ],
+            emitted_events()
         );
+
+        contract.relay_with_proof(fixture("btc_later"));
+        let resolve_time = 1_650_000_070_000_000_000;
+        assert_eq!(Some((20_100 * E9, resolve_time, 1002)), contract.get_refs("BTC".into()));
+
+        // replaying an older proof is skipped like any outdated relay
+        take_emitted();
+        contract.relay_with_proof(fixture("btc_eth"));
+        assert_eq!(Some((20_100 * E9, resolve_time, 1002)), contract.get_refs("BTC".into()));
+        assert_eq!("relay_skip", emitted_events()[1]["event"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ORACLE_STATE_ROOT_MISMATCH")]
+    fn test_relay_with_proof_fail_because_tampered_result() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        set_up_bridge(&mut contract);
+        let mut proof: Proof = fixture("btc_eth");
+
+        // doubles the BTC rate
+        let rates = &mut proof.oracle_data_proof.result.result.0;
+        rates[4..12].copy_from_slice(&40_000_246_912u64.to_be_bytes());
+        contract.relay_with_proof(proof);
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_ENOUGH_VOTING_POWER")]
+    fn test_relay_with_proof_fail_because_tampered_block() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        set_up_bridge(&mut contract);
+        let mut proof: Proof = fixture("btc_eth");
+
+        // the signatures now recover to keys outside of the validator set, sorted here
+        // so that they are not refused for their order instead
+        let block = &mut proof.block_relay_proof;
+        block.block_header_merkle_parts.height = U64(5002);
+        block.common_encoded_vote_part.signed_data_prefix.0[3..11]
+            .copy_from_slice(&5002u64.to_le_bytes());
+        let app_hash = block.multi_store_proof.app_hash().unwrap();
+        let block_hash = block.block_header_merkle_parts.block_hash(&app_hash).unwrap();
+        let common = block.common_encoded_vote_part.clone();
+        let chain_id = contract.get_bridge_config().unwrap().chain_id;
+        block.signatures.sort_by_key(|signature| {
+            signature.recover_signer(&block_hash, &common, &chain_id).unwrap()
+        });
+        contract.relay_with_proof(proof);
+    }
+
+    #[test]
+    #[should_panic(expected = "INVALID_VOTE_PART")]
+    fn test_relay_with_proof_fail_because_prevote() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        set_up_bridge(&mut contract);
+        let mut proof: Proof = fixture("btc_eth");
+
+        proof.block_relay_proof.common_encoded_vote_part.signed_data_prefix.0[1] = 1;
+        contract.relay_with_proof(proof);
+    }
+
+    #[test]
+    #[should_panic(expected = "INVALID_VOTE_PART")]
+    fn test_relay_with_proof_fail_because_vote_for_another_height() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        set_up_bridge(&mut contract);
+        let mut proof: Proof = fixture("btc_eth");
+
+        let block = &mut proof.block_relay_proof;
+        let height = block.block_header_merkle_parts.height.0 + 1;
+        block.common_encoded_vote_part.signed_data_prefix.0[3..11]
+            .copy_from_slice(&height.to_le_bytes());
+        contract.relay_with_proof(proof);
+    }
+
+    #[test]
+    #[should_panic(expected = "INVALID_VOTE_PART")]
+    fn test_relay_with_proof_fail_because_bad_vote_part_length() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        set_up_bridge(&mut contract);
+        let mut proof: Proof = fixture("btc_eth");
+
+        proof.block_relay_proof.common_encoded_vote_part.signed_data_suffix.0.push(0);
+        contract.relay_with_proof(proof);
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_ENOUGH_VOTING_POWER")]
+    fn test_relay_with_proof_fail_because_not_enough_voting_power() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        set_up_bridge(&mut contract);
+
+        // two of the three signers hold exactly two thirds of the remaining power
+        let (signer, _) = contract.get_validators()[0].clone();
+        contract.update_validator_powers(vec![(signer, U64(0))]);
+        contract.relay_with_proof(fixture("btc_eth"));
+    }
+
+    #[test]
+    #[should_panic(expected = "UNSORTED_SIGNERS")]
+    fn test_relay_with_proof_fail_because_repeated_signature() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        set_up_bridge(&mut contract);
+        let mut proof: Proof = fixture("btc_eth");
+
+        let signatures = &mut proof.block_relay_proof.signatures;
+        signatures.push(signatures[0].clone());
+        contract.relay_with_proof(proof);
+    }
+
+    #[test]
+    #[should_panic(expected = "UNEXPECTED_ORACLE_SCRIPT:3")]
+    fn test_relay_with_proof_fail_because_unexpected_oracle_script() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        set_up_bridge(&mut contract);
+        let mut config = contract.get_bridge_config().unwrap();
+        config.oracle_script_id = U64(4);
+        contract.set_bridge_config(config);
+
+        contract.relay_with_proof(fixture("btc_eth"));
+    }
+
+    #[test]
+    #[should_panic(expected = "BRIDGE_NOT_CONFIGURED")]
+    fn test_relay_with_proof_fail_because_not_configured() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+
+        contract.relay_with_proof(fixture("btc_eth"));
+    }
+
+    #[test]
+    #[should_panic(expected = "UNREGISTERED_SYMBOL:ETH")]
+    fn test_relay_with_proof_fail_because_unregistered_symbol() {
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context);
+        let mut contract = StdReferenceBasic::new();
+        set_up_bridge(&mut contract);
+        contract.delete_symbol("ETH".into());
+
+        contract.relay_with_proof(fixture("btc_eth"));
+    }
+
+    #[test]
+    fn test_relay_with_proof_gas_for_large_validator_set() {
+        // Base cost of the `ecrecover` host function in the NEAR runtime config.
+        const ECRECOVER_BASE_GAS: u64 = 278_821_988_457;
+        const TGAS: u64 = 1_000_000_000_000;
+        let mut context = get_context();
+        context.predecessor_account_id = bob();
+        testing_env!(context.clone());
+        let mut contract = StdReferenceBasic::new();
+        set_up_bridge(&mut contract);
+        let large_set: Value = fixture("large_set");
+        let validators: Vec<(Base64VecU8, U64)> =
+            serde_json::from_value(large_set["validators"].clone()).unwrap();
+        contract.update_validator_powers(validators);
+        let proof: Proof = serde_json::from_value(large_set["proof"].clone()).unwrap();
+        let signatures = proof.block_relay_proof.signatures.len() as u64;
+        assert_eq!(90, signatures);
+
+        context.predecessor_account_id = carol();
+        context.storage_usage = env::storage_usage();
+        testing_env!(context);
+        contract.relay_with_proof(proof);
+        let btc = Some((20_200 * E9, 1_650_000_130_000_000_000, 1003));
+        assert_eq!(btc, contract.get_refs("BTC".into()));
+
+        // The mocked blockchain only meters host functions, and unit tests recover signers
+        // natively instead of through `ecrecover`. Adding its base cost per signature, the
+        // metered gas has to leave most of the 300 Tgas limit to the wasm execution.
+        let gas = env::used_gas() + signatures * ECRECOVER_BASE_GAS;
+        assert!(gas < 100 * TGAS, "{} gas", gas);
     }
 }
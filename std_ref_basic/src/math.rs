@@ -0,0 +1,148 @@
+/// Computes `a * b / c` with a 256-bit intermediate product, rounding down.
+/// Returns `None` when `c` is zero or when the quotient does not fit in `u128`.
+pub fn mul_div(a: u128, b: u128, c: u128) -> Option<u128> {
+    if c == 0 {
+        return None;
+    }
+    let (hi, lo) = full_mul(a, b);
+    if hi >= c {
+        return None;
+    }
+    // Long division of (hi, lo) by c. The remainder is always less than c, so the
+    // quotient fits in 128 bits. A bit carried out of `rem` means the shifted value
+    // is at least 2^128 > c and the wrapping subtraction yields the true remainder.
+    let mut rem = hi;
+    let mut quo = 0u128;
+    for i in (0..128).rev() {
+        let carry = rem >> 127;
+        rem = (rem << 1) | ((lo >> i) & 1);
+        quo <<= 1;
+        if carry == 1 || rem >= c {
+            rem = rem.wrapping_sub(c);
+            quo |= 1;
+        }
+    }
+    Some(quo)
+}
+
+/// Relative change from `from` to `to` in basis points, rounding down. Returns `None`
+/// for a change from zero, or when the result does not fit in `u128`.
+pub fn deviation_bps(from: u128, to: u128) -> Option<u128> {
+    if from == to {
+        return Some(0);
+    }
+    mul_div(from.max(to) - from.min(to), 10_000, from)
+}
+
+/// Returns the full 256-bit product of `a` and `b` as `(high, low)` 128-bit halves.
+fn full_mul(a: u128, b: u128) -> (u128, u128) {
+    let mask = u64::MAX as u128;
+    let (a1, a0) = (a >> 64, a & mask);
+    let (b1, b0) = (b >> 64, b & mask);
+
+    let p00 = a0 * b0;
+    let p01 = a0 * b1;
+    let p10 = a1 * b0;
+    let p11 = a1 * b1;
+
+    let mid = (p00 >> 64) + (p01 & mask) + (p10 & mask);
+    let lo = (mid << 64) | (p00 & mask);
+    let hi = p11 + (p01 >> 64) + (p10 >> 64) + (mid >> 64);
+    (hi, lo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    fn extreme() -> impl Strategy<Value = u128> {
+        prop_oneof![
+            Just(0),
+            Just(1),
+            Just(u64::MAX as u128),
+            Just(u64::MAX as u128 + 1),
+            Just(u128::MAX - 1),
+            Just(u128::MAX),
+            any::<u64>().prop_map(u128::from),
+            any::<u128>(),
+        ]
+    }
+
+    #[test]
+    fn test_mul_div_small_values() {
+        assert_eq!(Some(6), mul_div(2, 3, 1));
+        assert_eq!(Some(2), mul_div(2, 3, 3));
+        assert_eq!(Some(1), mul_div(2, 3, 4));
+        assert_eq!(Some(0), mul_div(0, 3, 4));
+        assert_eq!(None, mul_div(2, 3, 0));
+    }
+
+    #[test]
+    fn test_mul_div_overflowing_intermediate() {
+        // 3.4e20 * 1e18 overflows u128 but the quotient does not
+        let rate = 340_000_000_000_000_000_000u128;
+        assert_eq!(Some(rate), mul_div(rate, E18, E18));
+        assert_eq!(Some(rate * 1_000), mul_div(rate, E18, E18 / 1_000));
+        assert_eq!(Some(u128::MAX), mul_div(u128::MAX, u128::MAX, u128::MAX));
+        assert_eq!(Some(u128::MAX / 2), mul_div(u128::MAX, E18, 2 * E18));
+    }
+
+    #[test]
+    fn test_mul_div_quotient_overflow() {
+        assert_eq!(None, mul_div(u128::MAX, 2, 1));
+        assert_eq!(None, mul_div(u128::MAX, E18, E18 - 1));
+        assert_eq!(None, mul_div(u128::MAX, u128::MAX, 1));
+    }
+
+    #[test]
+    fn test_deviation_bps() {
+        assert_eq!(Some(0), deviation_bps(0, 0));
+        assert_eq!(Some(0), deviation_bps(60_000, 60_000));
+        assert_eq!(Some(1_000), deviation_bps(60_000, 66_000));
+        assert_eq!(Some(9_999), deviation_bps(60_000, 6));
+        assert_eq!(Some(33), deviation_bps(30_000, 30_100));
+        assert_eq!(None, deviation_bps(0, 1));
+        assert_eq!(None, deviation_bps(1, u128::MAX));
+    }
+
+    #[test]
+    fn test_full_mul() {
+        assert_eq!((0, 6), full_mul(2, 3));
+        assert_eq!((1, 0), full_mul(1 << 64, 1 << 64));
+        assert_eq!((u128::MAX - 1, 1), full_mul(u128::MAX, u128::MAX));
+    }
+
+    proptest! {
+        #[test]
+        fn prop_mul_div_matches_checked_math(a in any::<u64>(), b in any::<u64>(), c in 1..=u128::MAX) {
+            let (a, b) = (u128::from(a), u128::from(b));
+            prop_assert_eq!(Some(a * b / c), mul_div(a, b, c));
+        }
+
+        #[test]
+        fn prop_mul_div_is_floor_of_quotient(a in extreme(), b in extreme(), c in extreme()) {
+            match mul_div(a, b, c) {
+                Some(q) => {
+                    // q * c <= a * b < (q + 1) * c
+                    let (hi, lo) = full_mul(a, b);
+                    let (qhi, qlo) = full_mul(q, c);
+                    prop_assert!((qhi, qlo) <= (hi, lo));
+                    let (rhi, rlo) = (hi - qhi - (lo < qlo) as u128, lo.wrapping_sub(qlo));
+                    prop_assert!(rhi == 0 && rlo < c);
+                }
+                None => {
+                    let (hi, _) = full_mul(a, b);
+                    prop_assert!(c == 0 || hi >= c);
+                }
+            }
+        }
+
+        #[test]
+        fn prop_mul_div_identity(a in extreme(), c in 1..=u128::MAX) {
+            prop_assert_eq!(Some(a), mul_div(a, c, c));
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{UnorderedMap, Vector};
+
+/// The order in which symbols were first relayed.
+///
+/// Each symbol keeps the position it was inserted at. Removing a symbol empties its
+/// position instead of moving the later ones, so that it takes constant time and a
+/// listing paged through meanwhile neither skips nor repeats a symbol. A symbol inserted
+/// again after its removal takes a new position at the end.
+#[derive(Default, BorshDeserialize, BorshSerialize)]
+pub struct RelayOrder {
+    symbols: Vector<Option<String>>,
+    positions: UnorderedMap<String, u64>,
+}
+
+impl RelayOrder {
+    pub fn new(prefix: &[u8]) -> Self {
+        Self {
+            symbols: Vector::new([prefix, b"s"].concat()),
+            positions: UnorderedMap::new([prefix, b"p"].concat()),
+        }
+    }
+
+    /// Number of positions, empty ones included.
+    pub fn len(&self) -> u64 {
+        self.symbols.len()
+    }
+
+    pub fn insert(&mut self, symbol: &String) {
+        if self.positions.get(symbol).is_none() {
+            self.positions.insert(symbol, &self.symbols.len());
+            self.symbols.push(&Some(symbol.clone()));
+        }
+    }
+
+    pub fn remove(&mut self, symbol: &String) {
+        if let Some(position) = self.positions.remove(symbol) {
+            self.symbols.replace(position, &None);
+        }
+    }
+
+    /// Returns the symbols at positions `from..from + limit`, skipping empty ones.
+    pub fn range(&self, from: u64, limit: u64) -> Vec<String> {
+        let end = from.saturating_add(limit).min(self.len());
+        (from.min(end)..end).filter_map(|position| self.symbols.get(position).unwrap()).collect()
+    }
+
+    // Lets tests write the state of versions without it.
+    #[cfg(test)]
+    pub fn clear(&mut self) {
+        self.symbols.clear();
+        self.positions.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::get_context;
+    use near_sdk::testing_env;
+    use near_sdk::MockedBlockchain;
+
+    #[test]
+    fn test_insert_and_remove() {
+        testing_env!(get_context());
+        let mut order = RelayOrder::new(b"o");
+        for symbol in &["BTC", "ETH", "BAND"] {
+            order.insert(&symbol.to_string());
+        }
+        // inserting again keeps the position
+        order.insert(&"BTC".to_string());
+        assert_eq!(vec!["BTC", "ETH", "BAND"], order.range(0, 10));
+
+        // the later symbols keep their positions
+        order.remove(&"ETH".to_string());
+        assert_eq!(3, order.len());
+        assert_eq!(vec!["BTC"], order.range(0, 2));
+        assert_eq!(vec!["BAND"], order.range(2, 2));
+
+        order.insert(&"ETH".to_string());
+        assert_eq!(vec!["BTC", "BAND", "ETH"], order.range(0, 10));
+        assert_eq!(Vec::<String>::new(), order.range(4, u64::MAX));
+    }
+}
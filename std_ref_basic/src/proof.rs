@@ -0,0 +1,574 @@
+//! Proofs of BandChain oracle results.
+//!
+//! A proof follows the layout of the proofs served by BandChain for its EVM `Bridge`
+//! contract. The result is a leaf of the IAVL tree of the oracle store, whose root is
+//! one of the stores committed to by the app hash. The app hash is in turn one of the
+//! fields of the block header, whose hash is signed by the validators. Every hash is a
+//! sha256, and inner nodes of the simple Merkle trees are prefixed with 1, leaves with 0.
+
+use near_sdk::env;
+use near_sdk::json_types::{Base64VecU8, U64};
+use serde::{Deserialize, Serialize};
+use std::convert::TryInto;
+use std_reference_interface::errors::Error;
+
+/// `resolve_status` of a successfully resolved request.
+pub const RESOLVE_STATUS_SUCCESS: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Proof {
+    pub block_relay_proof: BlockRelayProof,
+    pub oracle_data_proof: OracleDataProof,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BlockRelayProof {
+    pub multi_store_proof: MultiStoreProof,
+    pub block_header_merkle_parts: BlockHeaderMerkleParts,
+    pub common_encoded_vote_part: CommonEncodedVotePart,
+    /// Precommit signatures, sorted by the compressed public key of their signer.
+    pub signatures: Vec<TmSignature>,
+}
+
+/// The app hash, less the oracle store.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MultiStoreProof {
+    pub acc_to_gov_stores_merkle_hash: Base64VecU8,
+    pub main_and_mint_stores_merkle_hash: Base64VecU8,
+    /// Root of the IAVL tree of the oracle store.
+    pub oracle_iavl_state_hash: Base64VecU8,
+    pub params_stores_merkle_hash: Base64VecU8,
+    pub slashing_to_upgrade_stores_merkle_hash: Base64VecU8,
+}
+
+/// The block header, less the app hash.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BlockHeaderMerkleParts {
+    pub version_and_chain_id_hash: Base64VecU8,
+    pub height: U64,
+    pub time_second: U64,
+    pub time_nano_second: u32,
+    pub last_block_id_and_other: Base64VecU8,
+    pub next_validator_hash_and_consensus_hash: Base64VecU8,
+    pub last_results_hash: Base64VecU8,
+    pub evidence_and_proposer_hash: Base64VecU8,
+}
+
+/// The encoded precommit vote, less the block hash and the parts that differ between
+/// validators.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CommonEncodedVotePart {
+    pub signed_data_prefix: Base64VecU8,
+    pub signed_data_suffix: Base64VecU8,
+}
+
+impl CommonEncodedVotePart {
+    /// Checks that the parts encode a precommit for the block at `height`, as Tendermint
+    /// canonically encodes it. Otherwise a prevote, or a vote for another height, would
+    /// be counted as a commit of the block.
+    pub fn check(&self, height: u64) -> Result<(), Error> {
+        let prefix = &self.signed_data_prefix.0;
+        let suffix = &self.signed_data_suffix.0;
+        // The precommit type and the height, then the round unless it is 0, then the
+        // block id up to its hash.
+        let precommit = [&[0x08, 0x02, 0x11][..], &height.to_le_bytes()].concat();
+        let valid_round = match prefix.len() {
+            15 => true,
+            24 => prefix[11] == 0x19,
+            _ => false,
+        };
+        let valid_prefix = valid_round
+            && prefix.starts_with(&precommit)
+            && prefix.ends_with(&[0x22, 0x48, 0x0a, 0x20]);
+        // The part set header of the block id: its total, then its hash.
+        let valid_suffix =
+            suffix.len() == 38 && suffix[..3] == [0x12, 0x24, 0x08] && suffix[4..6] == [0x12, 0x20];
+        if !valid_prefix || !valid_suffix {
+            return Err(Error::InvalidVotePart);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TmSignature {
+    pub r: Base64VecU8,
+    pub s: Base64VecU8,
+    /// Recovery id plus 27.
+    pub v: u8,
+    pub encoded_timestamp: Base64VecU8,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OracleDataProof {
+    pub result: OracleResult,
+    /// Version of the IAVL leaf holding the result.
+    pub version: U64,
+    /// Siblings from the leaf up to the root.
+    pub merkle_paths: Vec<IavlMerklePath>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct IavlMerklePath {
+    pub is_data_on_right: bool,
+    pub subtree_height: u8,
+    pub subtree_size: U64,
+    pub subtree_version: U64,
+    pub sibling_hash: Base64VecU8,
+}
+
+/// The result of an oracle request, as stored by BandChain.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OracleResult {
+    pub client_id: String,
+    pub oracle_script_id: U64,
+    /// OBI-encoded input of the oracle script.
+    pub calldata: Base64VecU8,
+    pub ask_count: U64,
+    pub min_count: U64,
+    pub request_id: U64,
+    pub ans_count: U64,
+    /// In seconds.
+    pub request_time: U64,
+    /// In seconds.
+    pub resolve_time: U64,
+    pub resolve_status: u32,
+    /// OBI-encoded output of the oracle script.
+    pub result: Base64VecU8,
+}
+
+impl MultiStoreProof {
+    pub fn app_hash(&self) -> Result<[u8; 32], Error> {
+        let oracle_leaf = [
+            &[6u8][..],
+            b"oracle",
+            &[32],
+            &env::sha256(hash(&self.oracle_iavl_state_hash)?),
+        ]
+        .concat();
+        Ok(inner_hash(
+            &inner_hash(
+                hash(&self.acc_to_gov_stores_merkle_hash)?,
+                &inner_hash(
+                    hash(&self.main_and_mint_stores_merkle_hash)?,
+                    &inner_hash(
+                        &leaf_hash(&oracle_leaf),
+                        hash(&self.params_stores_merkle_hash)?,
+                    ),
+                ),
+            ),
+            hash(&self.slashing_to_upgrade_stores_merkle_hash)?,
+        ))
+    }
+}
+
+impl BlockHeaderMerkleParts {
+    pub fn block_hash(&self, app_hash: &[u8; 32]) -> Result<[u8; 32], Error> {
+        let height = [&[8u8][..], &varint(self.height.0)].concat();
+        let app_hash = [&[10u8, 32][..], app_hash].concat();
+        Ok(inner_hash(
+            &inner_hash(
+                &inner_hash(
+                    hash(&self.version_and_chain_id_hash)?,
+                    &inner_hash(&leaf_hash(&height), &leaf_hash(&self.encoded_time())),
+                ),
+                &inner_hash(
+                    hash(&self.last_block_id_and_other)?,
+                    hash(&self.next_validator_hash_and_consensus_hash)?,
+                ),
+            ),
+            &inner_hash(
+                &inner_hash(&leaf_hash(&app_hash), hash(&self.last_results_hash)?),
+                hash(&self.evidence_and_proposer_hash)?,
+            ),
+        ))
+    }
+
+    // A protobuf `Timestamp`, omitting zero nanoseconds.
+    fn encoded_time(&self) -> Vec<u8> {
+        let mut encoded = [&[8u8][..], &varint(self.time_second.0)].concat();
+        if self.time_nano_second > 0 {
+            encoded.push(16);
+            encoded.extend(varint(self.time_nano_second as u64));
+        }
+        encoded
+    }
+}
+
+impl TmSignature {
+    /// Recovers the compressed public key that signed the precommit for `block_hash`.
+    /// `common` must have been checked against the height of the block.
+    pub fn recover_signer(
+        &self,
+        block_hash: &[u8; 32],
+        common: &CommonEncodedVotePart,
+        chain_id: &str,
+    ) -> Result<[u8; 33], Error> {
+        let timestamp = &self.encoded_timestamp.0;
+        let vote = [
+            &common.signed_data_prefix.0[..],
+            block_hash,
+            &common.signed_data_suffix.0,
+            &[42],
+            &varint(timestamp.len() as u64),
+            timestamp,
+            &[50],
+            &varint(chain_id.len() as u64),
+            chain_id.as_bytes(),
+        ]
+        .concat();
+        let sign_bytes = [varint(vote.len() as u64), vote].concat();
+        let message = to_hash(env::sha256(&sign_bytes));
+        let signature = [&hash(&self.r)?[..], hash(&self.s)?].concat().try_into().unwrap();
+        let recovery_id = match self.v {
+            27..=30 => self.v - 27,
+            _ => return Err(Error::InvalidSignature),
+        };
+        recover(&message, &signature, recovery_id)
+    }
+}
+
+// Recovers the compressed public key that produced the 64-byte `signature` (r, s) of
+// `message`, refusing high-s signatures, which BandChain validators do not produce.
+// Contracts use the `ecrecover` host function, which is far cheaper in gas and code
+// size than recovering in wasm. It is not available in unit tests.
+#[cfg(target_arch = "wasm32")]
+fn recover(message: &[u8; 32], signature: &[u8; 64], recovery_id: u8) -> Result<[u8; 33], Error> {
+    extern "C" {
+        fn ecrecover(
+            hash_len: u64,
+            hash_ptr: u64,
+            sig_len: u64,
+            sig_ptr: u64,
+            v: u64,
+            malleability_flag: u64,
+            register_id: u64,
+        ) -> u64;
+    }
+    const REGISTER: u64 = 0;
+    let recovered = unsafe {
+        ecrecover(
+            message.len() as _,
+            message.as_ptr() as _,
+            signature.len() as _,
+            signature.as_ptr() as _,
+            recovery_id as _,
+            1,
+            REGISTER,
+        )
+    };
+    if recovered != 1 {
+        return Err(Error::InvalidSignature);
+    }
+    let key = env::read_register(REGISTER).ok_or(Error::InvalidSignature)?;
+    Ok(compress(&key.try_into().map_err(|_| Error::InvalidSignature)?))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn recover(message: &[u8; 32], signature: &[u8; 64], recovery_id: u8) -> Result<[u8; 33], Error> {
+    let message = libsecp256k1::Message::parse(message);
+    let signature =
+        libsecp256k1::Signature::parse_standard(signature).map_err(|_| Error::InvalidSignature)?;
+    if signature.s.is_high() {
+        return Err(Error::InvalidSignature);
+    }
+    let recovery_id =
+        libsecp256k1::RecoveryId::parse(recovery_id).map_err(|_| Error::InvalidSignature)?;
+    libsecp256k1::recover(&message, &signature, &recovery_id)
+        .map(|key| compress(key.serialize()[1..].try_into().unwrap()))
+        .map_err(|_| Error::InvalidSignature)
+}
+
+// Compresses an uncompressed public key, given without its 0x04 prefix as returned by
+// `ecrecover`: the x coordinate, prefixed with 2 for an even y and 3 for an odd one.
+fn compress(key: &[u8; 64]) -> [u8; 33] {
+    let mut compressed = [0u8; 33];
+    compressed[0] = 2 + (key[63] & 1);
+    compressed[1..].copy_from_slice(&key[..32]);
+    compressed
+}
+
+impl OracleDataProof {
+    /// Root of the IAVL tree of the oracle store that holds `result`.
+    pub fn oracle_state_root(&self) -> Result<[u8; 32], Error> {
+        let result = &self.result;
+        let leaf = [
+            &[0u8, 2][..],
+            &signed_varint(self.version.0)?,
+            &[9, 255],
+            &result.request_id.0.to_be_bytes(),
+            &[32],
+            &env::sha256(&result.encode()),
+        ]
+        .concat();
+        self.merkle_paths.iter().try_fold(to_hash(env::sha256(&leaf)), |hash, path| {
+            path.parent_hash(&hash)
+        })
+    }
+}
+
+impl IavlMerklePath {
+    fn parent_hash(&self, child: &[u8; 32]) -> Result<[u8; 32], Error> {
+        let sibling = hash(&self.sibling_hash)?;
+        let (left, right) = if self.is_data_on_right { (sibling, child) } else { (child, sibling) };
+        let node = [
+            &[self.subtree_height << 1][..],
+            &signed_varint(self.subtree_size.0)?,
+            &signed_varint(self.subtree_version.0)?,
+            &[32],
+            left,
+            &[32],
+            right,
+        ]
+        .concat();
+        Ok(to_hash(env::sha256(&node)))
+    }
+}
+
+impl OracleResult {
+    /// Protobuf encoding of the result, which omits fields holding their default value.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut encoded = vec![];
+        push_bytes(&mut encoded, 1, self.client_id.as_bytes());
+        push_uint(&mut encoded, 2, self.oracle_script_id.0);
+        push_bytes(&mut encoded, 3, &self.calldata.0);
+        for (field, value) in [
+            (4, self.ask_count.0),
+            (5, self.min_count.0),
+            (6, self.request_id.0),
+            (7, self.ans_count.0),
+            (8, self.request_time.0),
+            (9, self.resolve_time.0),
+            (10, self.resolve_status as u64),
+        ] {
+            push_uint(&mut encoded, field, value);
+        }
+        push_bytes(&mut encoded, 11, &self.result.0);
+        encoded
+    }
+
+    /// Decodes the input and output of the standard price oracle script,
+    /// `{symbols:[string],multiplier:u64}` and `{rates:[u64]}`.
+    pub fn prices(&self) -> Result<(Vec<String>, u64, Vec<u64>), Error> {
+        let mut calldata = Obi(&self.calldata.0);
+        let symbols = calldata.vec(|obi| obi.string())?;
+        let multiplier = calldata.u64()?;
+        let mut result = Obi(&self.result.0);
+        let rates = result.vec(|obi| obi.u64())?;
+        let trailing = !calldata.0.is_empty() || !result.0.is_empty();
+        if trailing || multiplier == 0 || rates.len() != symbols.len() {
+            return Err(Error::MalformedProof);
+        }
+        Ok((symbols, multiplier, rates))
+    }
+}
+
+fn push_bytes(encoded: &mut Vec<u8>, field: u8, value: &[u8]) {
+    if !value.is_empty() {
+        encoded.push(field << 3 | 2);
+        encoded.extend(varint(value.len() as u64));
+        encoded.extend(value);
+    }
+}
+
+fn push_uint(encoded: &mut Vec<u8>, field: u8, value: u64) {
+    if value != 0 {
+        encoded.push(field << 3);
+        encoded.extend(varint(value));
+    }
+}
+
+// Reads OBI values, which are big-endian, with strings and vectors prefixed by their
+// length as a `u32`.
+struct Obi<'a>(&'a [u8]);
+
+impl<'a> Obi<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.0.len() < len {
+            return Err(Error::MalformedProof);
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, Error> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        let len = self.u32()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| Error::MalformedProof)
+    }
+
+    fn vec<T>(&mut self, item: impl Fn(&mut Self) -> Result<T, Error>) -> Result<Vec<T>, Error> {
+        (0..self.u32()?).map(|_| item(self)).collect()
+    }
+}
+
+fn varint(mut value: u64) -> Vec<u8> {
+    let mut encoded = vec![];
+    while value >= 0x80 {
+        encoded.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    encoded.push(value as u8);
+    encoded
+}
+
+// Encodes a non-negative int64 as IAVL does, zigzagged into a varint. Values past the
+// int64 range cannot come from BandChain.
+fn signed_varint(value: u64) -> Result<Vec<u8>, Error> {
+    value.checked_mul(2).map(varint).ok_or(Error::MalformedProof)
+}
+
+fn hash(bytes: &Base64VecU8) -> Result<&[u8; 32], Error> {
+    bytes.0[..].try_into().map_err(|_| Error::MalformedProof)
+}
+
+fn to_hash(digest: Vec<u8>) -> [u8; 32] {
+    digest.try_into().unwrap()
+}
+
+fn leaf_hash(leaf: &[u8]) -> [u8; 32] {
+    to_hash(env::sha256(&[&[0u8][..], leaf].concat()))
+}
+
+fn inner_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    to_hash(env::sha256(&[&[1u8][..], left, right].concat()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::get_context;
+    use near_sdk::testing_env;
+    use near_sdk::MockedBlockchain;
+
+    fn result(calldata: Vec<u8>, output: Vec<u8>) -> OracleResult {
+        OracleResult {
+            client_id: "".into(),
+            oracle_script_id: U64(3),
+            calldata: calldata.into(),
+            ask_count: U64(0),
+            min_count: U64(0),
+            request_id: U64(300),
+            ans_count: U64(0),
+            request_time: U64(0),
+            resolve_time: U64(0),
+            resolve_status: RESOLVE_STATUS_SUCCESS,
+            result: output.into(),
+        }
+    }
+
+    #[test]
+    fn test_varint() {
+        assert_eq!(vec![0], varint(0));
+        assert_eq!(vec![127], varint(127));
+        assert_eq!(vec![0xac, 0x02], varint(300));
+        assert_eq!(vec![0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 1], varint(u64::MAX));
+    }
+
+    #[test]
+    fn test_encode_result_omits_defaults() {
+        assert_eq!(
+            vec![0x10, 3, 0x1a, 1, 7, 0x30, 0xac, 0x02, 0x50, 1],
+            result(vec![7], vec![]).encode()
+        );
+    }
+
+    #[test]
+    fn test_prices() {
+        let symbols = [&[0, 0, 0, 1, 0, 0, 0, 3][..], b"BTC"].concat();
+        let calldata = [symbols, vec![0, 0, 0, 0, 0, 0, 0, 100]].concat();
+        let output = vec![0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 42];
+        assert_eq!(
+            Ok((vec!["BTC".to_string()], 100, vec![42])),
+            result(calldata.clone(), output.clone()).prices()
+        );
+
+        // truncated, trailing bytes and a rate count that does not match the symbols
+        let malformed = Err(Error::MalformedProof);
+        assert_eq!(malformed, result(calldata.clone(), output[..11].to_vec()).prices());
+        assert_eq!(malformed, result(calldata.clone(), [&output[..], &[0]].concat()).prices());
+        assert_eq!(malformed, result(calldata, vec![0, 0, 0, 0]).prices());
+    }
+
+    #[test]
+    fn test_compress() {
+        let mut parities = vec![];
+        for secret in [1u8, 6] {
+            let secret = [&[0; 31][..], &[secret]].concat().try_into().unwrap();
+            let key = libsecp256k1::PublicKey::from_secret_key(
+                &libsecp256k1::SecretKey::parse(&secret).unwrap(),
+            );
+            let compressed = compress(key.serialize()[1..].try_into().unwrap());
+            assert_eq!(key.serialize_compressed(), compressed);
+            parities.push(compressed[0]);
+        }
+        // keys with an even and with an odd y
+        assert_eq!(vec![2, 3], parities);
+    }
+
+    #[test]
+    fn test_check_vote_part() {
+        let height = 5001u64.to_le_bytes();
+        let block_id = [0x22, 0x48, 0x0a, 0x20];
+        let prefix = [&[0x08, 0x02, 0x11][..], &height, &block_id].concat();
+        let suffix = [&[0x12, 0x24, 0x08, 0x01, 0x12, 0x20][..], &[0; 32]].concat();
+        let part = |prefix: &[u8], suffix: &[u8]| CommonEncodedVotePart {
+            signed_data_prefix: prefix.to_vec().into(),
+            signed_data_suffix: suffix.to_vec().into(),
+        };
+        assert_eq!(Ok(()), part(&prefix, &suffix).check(5001));
+
+        // a round other than 0
+        let round = [&[0x19][..], &2u64.to_le_bytes()].concat();
+        let with_round = [&prefix[..11], &round, &block_id].concat();
+        assert_eq!(Ok(()), part(&with_round, &suffix).check(5001));
+
+        // another height, a prevote, a missing block id and a truncated part set header
+        let invalid = Err(Error::InvalidVotePart);
+        assert_eq!(invalid, part(&prefix, &suffix).check(5002));
+        let prevote = [&[0x08, 0x01], &prefix[2..]].concat();
+        assert_eq!(invalid, part(&prevote, &suffix).check(5001));
+        assert_eq!(invalid, part(&prefix[..11], &suffix).check(5001));
+        assert_eq!(invalid, part(&prefix, &suffix[..37]).check(5001));
+    }
+
+    #[test]
+    fn test_malformed_hash() {
+        testing_env!(get_context());
+        let path = IavlMerklePath {
+            is_data_on_right: true,
+            subtree_height: 1,
+            subtree_size: U64(2),
+            subtree_version: U64(1),
+            sibling_hash: vec![0; 31].into(),
+        };
+        assert_eq!(Err(Error::MalformedProof), path.parent_hash(&[0; 32]));
+    }
+
+    #[test]
+    fn test_version_out_of_range() {
+        testing_env!(get_context());
+        let path = IavlMerklePath {
+            is_data_on_right: true,
+            subtree_height: 1,
+            subtree_size: U64(2),
+            subtree_version: U64(1 << 63),
+            sibling_hash: vec![0; 32].into(),
+        };
+        assert_eq!(Err(Error::MalformedProof), path.parent_hash(&[0; 32]));
+        let proof = OracleDataProof {
+            result: result(vec![], vec![]),
+            version: U64(u64::MAX),
+            merkle_paths: vec![],
+        };
+        assert_eq!(Err(Error::MalformedProof), proof.oracle_state_root());
+    }
+}
@@ -0,0 +1,205 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::collections::UnorderedMap;
+use near_sdk::json_types::{U128, U64};
+use near_sdk::AccountId;
+use serde::{Deserialize, Serialize};
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Submission {
+    pub feeder: AccountId,
+    /// Normalized the same way as relayed rates.
+    pub rate: U128,
+    pub resolve_time: U64,
+    /// Block timestamp of the submission.
+    pub submitted_at: U64,
+}
+
+/// Per-symbol rounds of feeder submissions.
+///
+/// Rounds of a symbol are numbered from 1. Submissions go to the open round, the one
+/// after the last finalized round, and a feeder submitting again replaces its earlier
+/// submission. Submissions older than `timeout` nanoseconds expire. The round is
+/// finalized as soon as it holds `quorum` submissions. Only the submissions of the open
+/// round and of the last finalized round are kept.
+#[derive(Default, BorshDeserialize, BorshSerialize)]
+pub struct Rounds {
+    pub quorum: u64,
+    pub timeout: u64,
+    submissions: UnorderedMap<(String, u64), Vec<Submission>>,
+    finalized: UnorderedMap<String, u64>,
+}
+
+impl Rounds {
+    pub fn new(prefix: &[u8], quorum: u64, timeout: u64) -> Self {
+        Self {
+            quorum,
+            timeout,
+            submissions: UnorderedMap::new([prefix, b"s"].concat()),
+            finalized: UnorderedMap::new([prefix, b"f"].concat()),
+        }
+    }
+
+    pub fn finalized(&self, symbol: &str) -> Option<u64> {
+        self.finalized.get(&symbol.to_string())
+    }
+
+    pub fn submissions(&self, symbol: &str, round: u64) -> Vec<Submission> {
+        self.submissions.get(&(symbol.to_string(), round)).unwrap_or_default()
+    }
+
+    /// Adds `submission` to the open round of `symbol`, first dropping the submissions
+    /// that expired or whose feeder is no longer `is_feeder`. Returns the id of that
+    /// round, with its submissions if they reached the quorum and finalized it.
+    pub fn submit(
+        &mut self,
+        symbol: &str,
+        submission: &Submission,
+        is_feeder: impl Fn(&AccountId) -> bool,
+    ) -> (u64, Option<Vec<Submission>>) {
+        let round = self.open_round(symbol);
+        let now = submission.submitted_at.0;
+        let mut submissions = self.submissions(symbol, round);
+        submissions.retain(|other| {
+            other.feeder != submission.feeder
+                && is_feeder(&other.feeder)
+                && now.saturating_sub(other.submitted_at.0) <= self.timeout
+        });
+        submissions.push(submission.clone());
+        self.submissions.insert(&(symbol.to_string(), round), &submissions);
+        if (submissions.len() as u64) < self.quorum {
+            return (round, None);
+        }
+        self.submissions.remove(&(symbol.to_string(), round - 1));
+        self.finalized.insert(&symbol.to_string(), &round);
+        (round, Some(submissions))
+    }
+
+    pub fn clear(&mut self, symbol: &str) {
+        let round = self.open_round(symbol);
+        self.submissions.remove(&(symbol.to_string(), round));
+        self.submissions.remove(&(symbol.to_string(), round - 1));
+        self.finalized.remove(&symbol.to_string());
+    }
+
+    fn open_round(&self, symbol: &str) -> u64 {
+        self.finalized(symbol).unwrap_or(0) + 1
+    }
+}
+
+/// Median of `rates`, rounded down to the mean of the two middle ones for an even count.
+pub fn median(mut rates: Vec<u128>) -> Option<u128> {
+    rates.sort_unstable();
+    let mid = rates.len() / 2;
+    match rates.len() {
+        0 => None,
+        len if len % 2 == 1 => Some(rates[mid]),
+        _ => Some(rates[mid - 1] + (rates[mid] - rates[mid - 1]) / 2),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::get_context;
+    use near_sdk::testing_env;
+    use near_sdk::MockedBlockchain;
+
+    fn submission(feeder: &str, rate: u128) -> Submission {
+        submission_at(feeder, rate, 20)
+    }
+
+    fn submission_at(feeder: &str, rate: u128, submitted_at: u64) -> Submission {
+        Submission {
+            feeder: feeder.to_string(),
+            rate: U128(rate),
+            resolve_time: U64(10),
+            submitted_at: U64(submitted_at),
+        }
+    }
+
+    fn anyone(_: &AccountId) -> bool {
+        true
+    }
+
+    #[test]
+    fn test_submit_until_quorum() {
+        testing_env!(get_context());
+        let mut rounds = Rounds::new(b"r", 2, 100);
+        let btc = "BTC";
+
+        assert_eq!((1, None), rounds.submit(btc, &submission("a", 1), anyone));
+        // submitting again replaces the earlier submission instead of counting twice
+        assert_eq!((1, None), rounds.submit(btc, &submission("a", 2), anyone));
+        assert_eq!(None, rounds.finalized(btc));
+        assert_eq!(
+            (1, Some(vec![submission("a", 2), submission("b", 3)])),
+            rounds.submit(btc, &submission("b", 3), anyone)
+        );
+        assert_eq!(Some(1), rounds.finalized(btc));
+
+        // the next submission opens round 2, round 1 stays readable until 2 is finalized
+        assert_eq!((2, None), rounds.submit(btc, &submission("b", 4), anyone));
+        assert_eq!(vec![submission("a", 2), submission("b", 3)], rounds.submissions(btc, 1));
+        assert_eq!(vec![submission("b", 4)], rounds.submissions(btc, 2));
+        assert_eq!(None, rounds.finalized("ETH"));
+
+        rounds.submit(btc, &submission("c", 5), anyone);
+        assert_eq!(Some(2), rounds.finalized(btc));
+        assert_eq!(Vec::<Submission>::new(), rounds.submissions(btc, 1));
+        assert_eq!(1, rounds.submissions.len());
+    }
+
+    #[test]
+    fn test_submit_drops_expired_submissions() {
+        testing_env!(get_context());
+        let mut rounds = Rounds::new(b"r", 2, 100);
+
+        rounds.submit("BTC", &submission_at("a", 1, 20), anyone);
+        assert_eq!((1, None), rounds.submit("BTC", &submission_at("b", 2, 121), anyone));
+        assert_eq!(vec![submission_at("b", 2, 121)], rounds.submissions("BTC", 1));
+
+        // a submission exactly `timeout` old still counts
+        assert_eq!(
+            (1, Some(vec![submission_at("b", 2, 121), submission_at("c", 3, 221)])),
+            rounds.submit("BTC", &submission_at("c", 3, 221), anyone)
+        );
+    }
+
+    #[test]
+    fn test_submit_drops_submissions_of_removed_feeders() {
+        testing_env!(get_context());
+        let mut rounds = Rounds::new(b"r", 2, 100);
+
+        rounds.submit("BTC", &submission("a", 1), anyone);
+        let is_feeder = |feeder: &AccountId| feeder != "a";
+        assert_eq!((1, None), rounds.submit("BTC", &submission("b", 2), is_feeder));
+        assert_eq!(vec![submission("b", 2)], rounds.submissions("BTC", 1));
+    }
+
+    #[test]
+    fn test_clear() {
+        testing_env!(get_context());
+        let mut rounds = Rounds::new(b"r", 1, 100);
+
+        rounds.submit("BTC", &submission("a", 1), anyone);
+        rounds.quorum = 2;
+        rounds.submit("BTC", &submission("a", 2), anyone);
+        rounds.submit("ETH", &submission("a", 3), anyone);
+        rounds.clear("BTC");
+
+        assert_eq!(None, rounds.finalized("BTC"));
+        assert_eq!(Vec::<Submission>::new(), rounds.submissions("BTC", 2));
+        assert_eq!(vec![submission("a", 3)], rounds.submissions("ETH", 1));
+        assert_eq!(1, rounds.submissions.len());
+        assert_eq!(0, rounds.finalized.len());
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(None, median(vec![]));
+        assert_eq!(Some(7), median(vec![7]));
+        assert_eq!(Some(5), median(vec![9, 1, 5]));
+        assert_eq!(Some(4), median(vec![8, 1, 3, 6]));
+        assert_eq!(Some(u128::MAX - 1), median(vec![u128::MAX, u128::MAX - 2]));
+    }
+}
@@ -0,0 +1,318 @@
+//! Ownership transfers, the guardian and pausing, shared by the contracts.
+//!
+//! Each contract keeps `owner`, `pending_owner`, `guardian` and `paused` in its own state
+//! and exposes the methods under the same names. Those methods pass the fields they
+//! touch to the functions here, which check the caller and emit the events.
+//!
+//! Ownership moves in two steps: the owner proposes a new owner, who has to accept.
+//! The guardian, or the owner, can pause the contract; only the owner can unpause it.
+
+use crate::errors::Error;
+use crate::events::EventStandard;
+use near_sdk::{env, AccountId};
+use serde_json::json;
+
+pub fn assert_owner(owner: &AccountId) {
+    assert!(&env::predecessor_account_id() == owner, "{}", Error::NotAnOwner);
+}
+
+pub fn assert_guardian(owner: &AccountId, guardian: &Option<AccountId>) {
+    let predecessor = env::predecessor_account_id();
+    assert!(
+        guardian.as_ref() == Some(&predecessor) || &predecessor == owner,
+        "{}", Error::NotAGuardian
+    );
+}
+
+/// Proposes `new_owner`, who becomes the owner only after calling `accept_ownership`.
+/// Replaces any earlier proposal.
+pub fn propose_ownership(
+    events: &EventStandard,
+    owner: &AccountId,
+    pending_owner: &mut Option<AccountId>,
+    new_owner: AccountId,
+) {
+    assert_owner(owner);
+    events.emit("propose_ownership", json!({"owner": owner, "pending_owner": new_owner}));
+    *pending_owner = Some(new_owner);
+}
+
+pub fn accept_ownership(
+    events: &EventStandard,
+    owner: &mut AccountId,
+    pending_owner: &mut Option<AccountId>,
+) {
+    let predecessor = env::predecessor_account_id();
+    assert!(pending_owner.as_ref() == Some(&predecessor), "{}", Error::NotAPendingOwner);
+    events.emit("transfer_ownership", json!({"old_owner": owner, "new_owner": predecessor}));
+    *owner = predecessor;
+    *pending_owner = None;
+}
+
+pub fn cancel_ownership_proposal(
+    events: &EventStandard,
+    owner: &AccountId,
+    pending_owner: &mut Option<AccountId>,
+) {
+    assert_owner(owner);
+    let pending_owner = pending_owner.take().unwrap_or_else(|| panic!("{}", Error::NoPendingOwner));
+    events.emit(
+        "cancel_ownership_proposal",
+        json!({"owner": owner, "pending_owner": pending_owner}),
+    );
+}
+
+pub fn set_guardian(
+    events: &EventStandard,
+    owner: &AccountId,
+    guardian: &mut Option<AccountId>,
+    new_guardian: Option<AccountId>,
+) {
+    assert_owner(owner);
+    events.emit("set_guardian", json!({"old_guardian": guardian, "new_guardian": new_guardian}));
+    *guardian = new_guardian;
+}
+
+pub fn pause(
+    events: &EventStandard,
+    owner: &AccountId,
+    guardian: &Option<AccountId>,
+    paused: &mut bool,
+) {
+    assert_guardian(owner, guardian);
+    assert!(!*paused, "{}", Error::Paused);
+    events.emit("pause", json!({ "by": env::predecessor_account_id() }));
+    *paused = true;
+}
+
+pub fn unpause(events: &EventStandard, owner: &AccountId, paused: &mut bool) {
+    assert_owner(owner);
+    assert!(*paused, "{}", Error::NotPaused);
+    events.emit("unpause", json!({ "by": env::predecessor_account_id() }));
+    *paused = false;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::take_emitted;
+    use near_sdk::MockedBlockchain;
+    use near_sdk::{testing_env, VMContext};
+
+    const EVENTS: EventStandard = EventStandard { standard: "test", version: "1.0.0" };
+
+    fn alice() -> AccountId {
+        "alice.near".to_string()
+    }
+
+    fn bob() -> AccountId {
+        "bob.near".to_string()
+    }
+
+    fn carol() -> AccountId {
+        "carol.near".to_string()
+    }
+
+    fn called_by(predecessor: AccountId) {
+        testing_env!(VMContext {
+            current_account_id: "contract.near".to_string(),
+            signer_account_id: predecessor.clone(),
+            signer_account_pk: vec![0, 1, 2],
+            predecessor_account_id: predecessor,
+            input: vec![],
+            block_index: 0,
+            block_timestamp: 0,
+            account_balance: 0,
+            account_locked_balance: 0,
+            storage_usage: 0,
+            attached_deposit: 0,
+            prepaid_gas: 10u64.pow(18),
+            random_seed: vec![0, 1, 2],
+            is_view: false,
+            output_data_receivers: vec![],
+            epoch_height: 0,
+        });
+    }
+
+    #[test]
+    fn test_transfer_ownership() {
+        take_emitted();
+        let mut owner = bob();
+        let mut pending_owner = None;
+
+        // propose alice, who has to accept before taking over
+        called_by(bob());
+        propose_ownership(&EVENTS, &owner, &mut pending_owner, alice());
+        assert_eq!(bob(), owner);
+        assert_eq!(Some(alice()), pending_owner);
+
+        called_by(alice());
+        accept_ownership(&EVENTS, &mut owner, &mut pending_owner);
+        assert_eq!(alice(), owner);
+        assert_eq!(None, pending_owner);
+        assert_eq!(
+            vec![
+                concat!(
+                    r#"EVENT_JSON:{"standard":"test","version":"1.0.0","#,
+                    r#""event":"propose_ownership","#,
+                    r#""data":[{"owner":"bob.near","pending_owner":"alice.near"}]}"#,
+                ),
+                concat!(
+                    r#"EVENT_JSON:{"standard":"test","version":"1.0.0","#,
+                    r#""event":"transfer_ownership","#,
+                    r#""data":[{"old_owner":"bob.near","new_owner":"alice.near"}]}"#,
+                ),
+            ],
+            take_emitted()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_propose_ownership_fail_because_not_owner() {
+        let mut owner = bob();
+        let mut pending_owner = None;
+        called_by(bob());
+        propose_ownership(&EVENTS, &owner, &mut pending_owner, alice());
+        called_by(alice());
+        accept_ownership(&EVENTS, &mut owner, &mut pending_owner);
+
+        // should fail because bob is not an owner anymore
+        called_by(bob());
+        propose_ownership(&EVENTS, &owner, &mut pending_owner, bob());
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_A_PENDING_OWNER")]
+    fn test_accept_ownership_fail_because_not_pending_owner() {
+        let mut owner = bob();
+        let mut pending_owner = None;
+        called_by(bob());
+        propose_ownership(&EVENTS, &owner, &mut pending_owner, alice());
+
+        called_by(carol());
+        accept_ownership(&EVENTS, &mut owner, &mut pending_owner);
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_A_PENDING_OWNER")]
+    fn test_cancel_ownership_proposal() {
+        take_emitted();
+        let mut owner = bob();
+        let mut pending_owner = None;
+        called_by(bob());
+        propose_ownership(&EVENTS, &owner, &mut pending_owner, alice());
+        cancel_ownership_proposal(&EVENTS, &owner, &mut pending_owner);
+        assert_eq!(None, pending_owner);
+        assert_eq!(
+            concat!(
+                r#"EVENT_JSON:{"standard":"test","version":"1.0.0","#,
+                r#""event":"cancel_ownership_proposal","#,
+                r#""data":[{"owner":"bob.near","pending_owner":"alice.near"}]}"#,
+            ),
+            take_emitted()[1]
+        );
+
+        // a cancelled proposal cannot be accepted
+        called_by(alice());
+        accept_ownership(&EVENTS, &mut owner, &mut pending_owner);
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_cancel_ownership_proposal_fail_because_not_owner() {
+        let owner = bob();
+        let mut pending_owner = None;
+        called_by(bob());
+        propose_ownership(&EVENTS, &owner, &mut pending_owner, alice());
+
+        called_by(alice());
+        cancel_ownership_proposal(&EVENTS, &owner, &mut pending_owner);
+    }
+
+    #[test]
+    #[should_panic(expected = "NO_PENDING_OWNER")]
+    fn test_cancel_ownership_proposal_fail_because_nothing_pending() {
+        called_by(bob());
+        cancel_ownership_proposal(&EVENTS, &bob(), &mut None);
+    }
+
+    #[test]
+    fn test_pause_and_unpause() {
+        take_emitted();
+        let owner = bob();
+        let mut guardian = None;
+        let mut paused = false;
+        called_by(bob());
+        set_guardian(&EVENTS, &owner, &mut guardian, Some(carol()));
+        assert_eq!(Some(carol()), guardian);
+
+        called_by(carol());
+        pause(&EVENTS, &owner, &guardian, &mut paused);
+        assert!(paused);
+
+        called_by(bob());
+        unpause(&EVENTS, &owner, &mut paused);
+        assert!(!paused);
+
+        // the owner can pause as well
+        pause(&EVENTS, &owner, &guardian, &mut paused);
+        assert!(paused);
+        assert_eq!(
+            vec![
+                concat!(
+                    r#"EVENT_JSON:{"standard":"test","version":"1.0.0","event":"set_guardian","#,
+                    r#""data":[{"old_guardian":null,"new_guardian":"carol.near"}]}"#,
+                ),
+                concat!(
+                    r#"EVENT_JSON:{"standard":"test","version":"1.0.0","event":"pause","#,
+                    r#""data":[{"by":"carol.near"}]}"#,
+                ),
+                concat!(
+                    r#"EVENT_JSON:{"standard":"test","version":"1.0.0","event":"unpause","#,
+                    r#""data":[{"by":"bob.near"}]}"#,
+                ),
+                concat!(
+                    r#"EVENT_JSON:{"standard":"test","version":"1.0.0","event":"pause","#,
+                    r#""data":[{"by":"bob.near"}]}"#,
+                ),
+            ],
+            take_emitted()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_set_guardian_fail_because_not_owner() {
+        called_by(carol());
+        set_guardian(&EVENTS, &bob(), &mut None, Some(carol()));
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_A_GUARDIAN")]
+    fn test_pause_fail_because_not_guardian() {
+        called_by(alice());
+        pause(&EVENTS, &bob(), &Some(carol()), &mut false);
+    }
+
+    #[test]
+    #[should_panic(expected = "PAUSED")]
+    fn test_pause_fail_because_paused() {
+        called_by(bob());
+        pause(&EVENTS, &bob(), &None, &mut true);
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_AN_OWNER")]
+    fn test_unpause_fail_because_not_owner() {
+        called_by(carol());
+        unpause(&EVENTS, &bob(), &mut true);
+    }
+
+    #[test]
+    #[should_panic(expected = "NOT_PAUSED")]
+    fn test_unpause_fail_because_not_paused() {
+        called_by(bob());
+        unpause(&EVENTS, &bob(), &mut false);
+    }
+}
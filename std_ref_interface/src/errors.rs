@@ -0,0 +1,263 @@
+//! Errors shared by the contracts.
+//!
+//! Panics and logs carry the `Display` form of an [`Error`]: its code, followed by
+//! its details separated by `:`, e.g. `UNREGISTERED_SYMBOL:BTC`. Codes are stable, so
+//! clients can branch on the part before the first `:`. Views that return a `Result`
+//! serialize the error as an object tagged by the same code, e.g.
+//! `{"code":"UNREGISTERED_SYMBOL","symbol":"BTC"}`.
+
+use near_sdk::json_types::{U128, U64};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "code", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Error {
+    AlreadyInitialized,
+    StateNotFound,
+    NotAnOwner,
+    BadInputLength,
+    NotAPendingOwner,
+    NoPendingOwner,
+    NotAGuardian,
+    Paused,
+    NotPaused,
+    UnsupportedStateVersion { version: u32 },
+    // StdReferenceBasic
+    NotARelayer,
+    AlreadyARelayer,
+    NotAFeeder,
+    AlreadyAFeeder,
+    BadRatesLength,
+    BadResolveTimesLength,
+    BadRequestIdsLength,
+    FailToParseRate { symbol: String, rate: String },
+    MaxAgeNotSet { symbol: String },
+    CannotRelayAnchor { symbol: String },
+    NotAnAnchor { symbol: String },
+    ZeroAnchorRate { symbol: String },
+    UnregisteredSymbol { symbol: String },
+    DeprecatedSymbol { symbol: String },
+    ZeroMultiplier { symbol: String },
+    RefDataNotAvailableFor { symbol: String },
+    RefDataTooOldFor { symbol: String },
+    ZeroRateFor { symbol: String },
+    /// `symbol` is either a relayed symbol or a `BASE/QUOTE` pair.
+    RateOverflowFor { symbol: String },
+    NotEnoughHistoryFor { symbol: String },
+    ZeroTwapWindow,
+    ZeroQuorum,
+    MaxDeviationNotSet { symbol: String },
+    NoPendingUpdateFor { symbol: String },
+    BridgeNotConfigured,
+    InvalidValidatorPubKey,
+    ValidatorPowerOverflow,
+    MalformedProof,
+    InvalidVotePart,
+    InvalidSignature,
+    UnsortedSigners,
+    NotEnoughVotingPower,
+    OracleStateRootMismatch,
+    UnexpectedOracleScript { oracle_script_id: U64 },
+    UnsuccessfulRequest { request_id: U64 },
+    // SimplePriceDB
+    NotAnAllowedCaller,
+    AlreadyAnAllowedCaller,
+    NotEnoughDeposit { deposit: U128, required: U128 },
+    NotEnoughCollectedFees,
+    NotASelfCall,
+    BadPromiseResultsCount,
+    BadOracleResultLength,
+    PromiseNotReady,
+    OracleCallFailed,
+    OracleReturnedNone,
+    OracleResultUnparsable,
+}
+
+impl Error {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::AlreadyInitialized => "ALREADY_INITIALIZED",
+            Error::StateNotFound => "STATE_NOT_FOUND",
+            Error::NotAnOwner => "NOT_AN_OWNER",
+            Error::BadInputLength => "BAD_INPUT_LENGTH",
+            Error::NotAPendingOwner => "NOT_A_PENDING_OWNER",
+            Error::NoPendingOwner => "NO_PENDING_OWNER",
+            Error::NotAGuardian => "NOT_A_GUARDIAN",
+            Error::Paused => "PAUSED",
+            Error::NotPaused => "NOT_PAUSED",
+            Error::UnsupportedStateVersion { .. } => "UNSUPPORTED_STATE_VERSION",
+            Error::NotARelayer => "NOT_A_RELAYER",
+            Error::AlreadyARelayer => "ALREADY_A_RELAYER",
+            Error::NotAFeeder => "NOT_A_FEEDER",
+            Error::AlreadyAFeeder => "ALREADY_A_FEEDER",
+            Error::BadRatesLength => "BAD_RATES_LENGTH",
+            Error::BadResolveTimesLength => "BAD_RESOLVE_TIMES_LENGTH",
+            Error::BadRequestIdsLength => "BAD_REQUEST_IDS_LENGTH",
+            Error::FailToParseRate { .. } => "FAIL_TO_PARSE_RATE",
+            Error::MaxAgeNotSet { .. } => "MAX_AGE_NOT_SET",
+            Error::CannotRelayAnchor { .. } => "CANNOT_RELAY_ANCHOR",
+            Error::NotAnAnchor { .. } => "NOT_AN_ANCHOR",
+            Error::ZeroAnchorRate { .. } => "ZERO_ANCHOR_RATE",
+            Error::UnregisteredSymbol { .. } => "UNREGISTERED_SYMBOL",
+            Error::DeprecatedSymbol { .. } => "DEPRECATED_SYMBOL",
+            Error::ZeroMultiplier { .. } => "ZERO_MULTIPLIER",
+            Error::RefDataNotAvailableFor { .. } => "REF_DATA_NOT_AVAILABLE_FOR",
+            Error::RefDataTooOldFor { .. } => "REF_DATA_TOO_OLD_FOR",
+            Error::ZeroRateFor { .. } => "ZERO_RATE_FOR",
+            Error::RateOverflowFor { .. } => "RATE_OVERFLOW_FOR",
+            Error::NotEnoughHistoryFor { .. } => "NOT_ENOUGH_HISTORY_FOR",
+            Error::ZeroTwapWindow => "ZERO_TWAP_WINDOW",
+            Error::ZeroQuorum => "ZERO_QUORUM",
+            Error::MaxDeviationNotSet { .. } => "MAX_DEVIATION_NOT_SET",
+            Error::NoPendingUpdateFor { .. } => "NO_PENDING_UPDATE_FOR",
+            Error::BridgeNotConfigured => "BRIDGE_NOT_CONFIGURED",
+            Error::InvalidValidatorPubKey => "INVALID_VALIDATOR_PUB_KEY",
+            Error::ValidatorPowerOverflow => "VALIDATOR_POWER_OVERFLOW",
+            Error::MalformedProof => "MALFORMED_PROOF",
+            Error::InvalidVotePart => "INVALID_VOTE_PART",
+            Error::InvalidSignature => "INVALID_SIGNATURE",
+            Error::UnsortedSigners => "UNSORTED_SIGNERS",
+            Error::NotEnoughVotingPower => "NOT_ENOUGH_VOTING_POWER",
+            Error::OracleStateRootMismatch => "ORACLE_STATE_ROOT_MISMATCH",
+            Error::UnexpectedOracleScript { .. } => "UNEXPECTED_ORACLE_SCRIPT",
+            Error::UnsuccessfulRequest { .. } => "UNSUCCESSFUL_REQUEST",
+            Error::NotAnAllowedCaller => "NOT_AN_ALLOWED_CALLER",
+            Error::AlreadyAnAllowedCaller => "ALREADY_AN_ALLOWED_CALLER",
+            Error::NotEnoughDeposit { .. } => "NOT_ENOUGH_DEPOSIT",
+            Error::NotEnoughCollectedFees => "NOT_ENOUGH_COLLECTED_FEES",
+            Error::NotASelfCall => "NOT_A_SELF_CALL",
+            Error::BadPromiseResultsCount => "BAD_PROMISE_RESULTS_COUNT",
+            Error::BadOracleResultLength => "BAD_ORACLE_RESULT_LENGTH",
+            Error::PromiseNotReady => "PROMISE_NOT_READY",
+            Error::OracleCallFailed => "ORACLE_CALL_FAILED",
+            Error::OracleReturnedNone => "ORACLE_RETURNED_NONE",
+            Error::OracleResultUnparsable => "ORACLE_RESULT_UNPARSABLE",
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())?;
+        match self {
+            Error::FailToParseRate { symbol, rate } => write!(f, ":{}:{}", symbol, rate),
+            Error::MaxAgeNotSet { symbol }
+            | Error::CannotRelayAnchor { symbol }
+            | Error::NotAnAnchor { symbol }
+            | Error::ZeroAnchorRate { symbol }
+            | Error::UnregisteredSymbol { symbol }
+            | Error::DeprecatedSymbol { symbol }
+            | Error::ZeroMultiplier { symbol }
+            | Error::RefDataNotAvailableFor { symbol }
+            | Error::RefDataTooOldFor { symbol }
+            | Error::ZeroRateFor { symbol }
+            | Error::RateOverflowFor { symbol }
+            | Error::NotEnoughHistoryFor { symbol }
+            | Error::MaxDeviationNotSet { symbol }
+            | Error::NoPendingUpdateFor { symbol } => write!(f, ":{}", symbol),
+            Error::UnsupportedStateVersion { version } => write!(f, ":{}", version),
+            Error::UnexpectedOracleScript { oracle_script_id: id }
+            | Error::UnsuccessfulRequest { request_id: id } => write!(f, ":{}", id.0),
+            Error::NotEnoughDeposit { deposit, required } => {
+                write!(f, ":{}:{}", deposit.0, required.0)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!("NOT_AN_OWNER", Error::NotAnOwner.to_string());
+        assert_eq!(
+            "UNREGISTERED_SYMBOL:BTC",
+            Error::UnregisteredSymbol { symbol: "BTC".into() }.to_string()
+        );
+        assert_eq!(
+            "FAIL_TO_PARSE_RATE:BTC:1.5",
+            Error::FailToParseRate { symbol: "BTC".into(), rate: "1.5".into() }.to_string()
+        );
+        assert_eq!(
+            "NOT_ENOUGH_DEPOSIT:1:30",
+            Error::NotEnoughDeposit { deposit: U128(1), required: U128(30) }.to_string()
+        );
+    }
+
+    #[test]
+    fn test_json_is_tagged_by_code() {
+        let symbol = || "BTC".to_string();
+        // every variant, so that `code` cannot drift from the serde tag
+        let errors = vec![
+            Error::AlreadyInitialized,
+            Error::StateNotFound,
+            Error::NotAnOwner,
+            Error::BadInputLength,
+            Error::NotAPendingOwner,
+            Error::NoPendingOwner,
+            Error::NotAGuardian,
+            Error::Paused,
+            Error::NotPaused,
+            Error::UnsupportedStateVersion { version: 2 },
+            Error::NotARelayer,
+            Error::AlreadyARelayer,
+            Error::NotAFeeder,
+            Error::AlreadyAFeeder,
+            Error::BadRatesLength,
+            Error::BadResolveTimesLength,
+            Error::BadRequestIdsLength,
+            Error::FailToParseRate { symbol: symbol(), rate: "x".into() },
+            Error::MaxAgeNotSet { symbol: symbol() },
+            Error::CannotRelayAnchor { symbol: symbol() },
+            Error::NotAnAnchor { symbol: symbol() },
+            Error::ZeroAnchorRate { symbol: symbol() },
+            Error::UnregisteredSymbol { symbol: symbol() },
+            Error::DeprecatedSymbol { symbol: symbol() },
+            Error::ZeroMultiplier { symbol: symbol() },
+            Error::RefDataNotAvailableFor { symbol: symbol() },
+            Error::RefDataTooOldFor { symbol: symbol() },
+            Error::ZeroRateFor { symbol: symbol() },
+            Error::RateOverflowFor { symbol: symbol() },
+            Error::NotEnoughHistoryFor { symbol: symbol() },
+            Error::ZeroTwapWindow,
+            Error::ZeroQuorum,
+            Error::MaxDeviationNotSet { symbol: symbol() },
+            Error::NoPendingUpdateFor { symbol: symbol() },
+            Error::BridgeNotConfigured,
+            Error::InvalidValidatorPubKey,
+            Error::ValidatorPowerOverflow,
+            Error::MalformedProof,
+            Error::InvalidVotePart,
+            Error::InvalidSignature,
+            Error::UnsortedSigners,
+            Error::NotEnoughVotingPower,
+            Error::OracleStateRootMismatch,
+            Error::UnexpectedOracleScript { oracle_script_id: U64(3) },
+            Error::UnsuccessfulRequest { request_id: U64(1) },
+            Error::NotAnAllowedCaller,
+            Error::AlreadyAnAllowedCaller,
+            Error::NotEnoughDeposit { deposit: U128(1), required: U128(2) },
+            Error::NotEnoughCollectedFees,
+            Error::NotASelfCall,
+            Error::BadPromiseResultsCount,
+            Error::BadOracleResultLength,
+            Error::PromiseNotReady,
+            Error::OracleCallFailed,
+            Error::OracleReturnedNone,
+            Error::OracleResultUnparsable,
+        ];
+        for error in errors {
+            let json = serde_json::to_value(&error).unwrap();
+            assert_eq!(error.code(), json["code"]);
+            assert_eq!(error, serde_json::from_value(json).unwrap());
+        }
+        assert_eq!(
+            r#"{"code":"REF_DATA_NOT_AVAILABLE_FOR","symbol":"BTC"}"#,
+            serde_json::to_string(&Error::RefDataNotAvailableFor { symbol: "BTC".into() }).unwrap()
+        );
+    }
+}
@@ -0,0 +1,75 @@
+//! Structured event logs in the NEP-297 format:
+//!
+//! `EVENT_JSON:{"standard":"std_proxy","version":"1.0.0","event":"set_ref","data":[...]}`
+//!
+//! `data` is always an array. Integers that do not fit in a JSON number are strings.
+
+use near_sdk::env;
+use serde::Serialize;
+use serde_json::Value;
+
+pub const EVENT_JSON_PREFIX: &str = "EVENT_JSON:";
+
+/// The `standard` and `version` of the events emitted by one contract.
+pub struct EventStandard {
+    pub standard: &'static str,
+    pub version: &'static str,
+}
+
+#[derive(Serialize)]
+struct Envelope<'a> {
+    standard: &'a str,
+    version: &'a str,
+    event: &'a str,
+    data: Value,
+}
+
+impl EventStandard {
+    pub fn to_log(&self, event: &str, data: Value) -> String {
+        let data = match data {
+            Value::Array(_) => data,
+            _ => Value::Array(vec![data]),
+        };
+        let envelope = Envelope { standard: self.standard, version: self.version, event, data };
+        format!("{}{}", EVENT_JSON_PREFIX, serde_json::to_string(&envelope).unwrap())
+    }
+
+    pub fn emit(&self, event: &str, data: Value) {
+        let log = self.to_log(event, data);
+        env::log(log.as_bytes());
+        #[cfg(not(target_arch = "wasm32"))]
+        EMITTED.with(|emitted| emitted.borrow_mut().push(log));
+    }
+}
+
+// The mocked blockchain does not expose the logs, so unit tests read the events from here.
+#[cfg(not(target_arch = "wasm32"))]
+thread_local! {
+    static EMITTED: std::cell::RefCell<Vec<String>> = const { std::cell::RefCell::new(vec![]) };
+}
+
+/// Returns and forgets the events emitted so far on this thread. Only available off-chain.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn take_emitted() -> Vec<String> {
+    EMITTED.with(|emitted| emitted.replace(vec![]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const EVENTS: EventStandard = EventStandard { standard: "test", version: "1.0.0" };
+
+    #[test]
+    fn test_to_log() {
+        assert_eq!(
+            r#"EVENT_JSON:{"standard":"test","version":"1.0.0","event":"set","data":[{"a":"1"}]}"#,
+            EVENTS.to_log("set", json!({"a": "1"}))
+        );
+        assert_eq!(
+            r#"EVENT_JSON:{"standard":"test","version":"1.0.0","event":"set","data":[1,2]}"#,
+            EVENTS.to_log("set", json!([1, 2]))
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{ext_contract, Promise};
+use serde::{Deserialize, Serialize};
+
+pub mod admin;
+pub mod errors;
+pub mod events;
+pub mod pagination;
+pub mod upgrade;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RefData {
+    pub rate: U128,
+    pub resolve_time: U64,
+    pub request_id: U64,
+}
+
+impl From<(u128, u64, u64)> for RefData {
+    fn from((rate, resolve_time, request_id): (u128, u64, u64)) -> Self {
+        Self { rate: rate.into(), resolve_time: resolve_time.into(), request_id: request_id.into() }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReferenceData {
+    pub rate: U128,
+    pub last_updated_base: U64,
+    pub last_updated_quote: U64,
+    /// The base, or the quote, is deprecated: its last reference is still served, but
+    /// it is no longer updated.
+    #[serde(default)]
+    pub deprecated_base: bool,
+    #[serde(default)]
+    pub deprecated_quote: bool,
+}
+
+impl From<(u128, u64, u64)> for ReferenceData {
+    fn from((rate, last_updated_base, last_updated_quote): (u128, u64, u64)) -> Self {
+        Self {
+            rate: rate.into(),
+            last_updated_base: last_updated_base.into(),
+            last_updated_quote: last_updated_quote.into(),
+            deprecated_base: false,
+            deprecated_quote: false,
+        }
+    }
+}
+
+// Lists the reference data views once so that the trait implemented by the oracle, the
+// bindings used to call it and the trait implemented by a proxy cannot drift apart.
+// `$define` receives `$args` followed by the signatures, without their receiver.
+macro_rules! std_reference_methods {
+    ($define:ident $args:tt) => {
+        $define! {
+            $args
+            fn get_reference_data(base: String, quote: String) -> Option<(u128, u64, u64)>;
+            fn get_reference_data_bulk(
+                bases: Vec<String>,
+                quotes: Vec<String>,
+            ) -> Option<Vec<(u128, u64, u64)>>;
+            fn get_reference_data_v2(base: String, quote: String) -> Option<ReferenceData>;
+            fn get_reference_data_bulk_v2(
+                bases: Vec<String>,
+                quotes: Vec<String>,
+            ) -> Option<Vec<ReferenceData>>;
+            /// Like `get_reference_data_bulk_v2`, but a pair that cannot be resolved only
+            /// turns its own item into `None`.
+            fn get_reference_data_bulk_partial(
+                bases: Vec<String>,
+                quotes: Vec<String>,
+            ) -> Vec<Option<ReferenceData>>;
+        }
+    };
+}
+
+macro_rules! std_reference {
+    (
+        { $(#[$attr:meta])* $name:ident }
+        $($(#[$method_attr:meta])* fn $method:ident($($params:tt)*) -> $ret:ty;)*
+    ) => {
+        $(#[$attr])*
+        pub trait $name {
+            $($(#[$method_attr])* fn $method(&self, $($params)*) -> $ret;)*
+        }
+    };
+}
+
+// Every view becomes a call that returns the promise of the forwarded view.
+macro_rules! std_reference_proxy {
+    (
+        { $(#[$attr:meta])* $name:ident }
+        $($(#[$method_attr:meta])* fn $method:ident($($params:tt)*) -> $ret:ty;)*
+    ) => {
+        $(#[$attr])*
+        pub trait $name {
+            $($(#[$method_attr])* fn $method(&mut self, $($params)*) -> Promise;)*
+        }
+    };
+}
+
+std_reference_methods!(std_reference {
+    /// Reference data views served by an oracle contract such as `StdReferenceBasic`.
+    StdReference
+});
+
+std_reference_methods!(std_reference {
+    /// Cross-contract bindings of `StdReference`, usable against the oracle or a proxy.
+    #[ext_contract(ext_std_reference)]
+    ExtStdReference
+});
+
+std_reference_methods!(std_reference_proxy {
+    /// A contract that forwards every `StdReference` view to an oracle and returns its
+    /// promise.
+    StdReferenceProxy
+});
@@ -0,0 +1,21 @@
+//! Paginated listing of the entries of a map, shared by the `list_*` views.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::collections::UnorderedMap;
+
+/// Returns up to `limit` entries of `map` starting at `from_index`, in insertion order.
+/// An index past the end yields no entries.
+///
+/// Entries of an `UnorderedMap` keep their index until one is removed, at which point
+/// the last entry takes the index of the removed one.
+pub fn paginate<K, V>(map: &UnorderedMap<K, V>, from_index: u64, limit: u64) -> Vec<(K, V)>
+where
+    K: BorshSerialize + BorshDeserialize,
+    V: BorshSerialize + BorshDeserialize,
+{
+    let keys = map.keys_as_vector();
+    let values = map.values_as_vector();
+    (from_index..keys.len().min(from_index.saturating_add(limit)))
+        .map(|index| (keys.get(index).unwrap(), values.get(index).unwrap()))
+        .collect()
+}
@@ -0,0 +1,88 @@
+//! Self-upgrades and the version of the stored state layout.
+//!
+//! Each contract records the version of its state layout under `STATE_VERSION`, next to
+//! the contract state. State written before versioning has no version, which reads as 0.
+//! An upgrade deploys the new code and calls its `migrate` in the same batch of actions,
+//! so a failing migration reverts the deployment as well.
+//!
+//! Each contract exposes:
+//!
+//! - `upgrade(code)`, callable by the owner, which deploys `code` to the contract account
+//!   and calls its `migrate` with the remaining gas, see `upgrade`.
+//! - `migrate()`, which converts the state stored by any earlier version of the contract
+//!   to the current layout and records the current version, see `migrate`. It must be
+//!   called by the owner or the contract account right after deploying the new code,
+//!   which `upgrade` does. Migrating state that already has the current layout only
+//!   records the version again. A version the contract does not know fails with
+//!   `UNSUPPORTED_STATE_VERSION` and leaves the state untouched.
+//! - `get_state_version()`, the version recorded for the stored state.
+
+use crate::admin;
+use crate::errors::Error;
+use crate::events::EventStandard;
+use borsh::BorshDeserialize;
+use near_sdk::{env, AccountId, Gas, Promise};
+use serde_json::json;
+use std::convert::TryInto;
+
+const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+// Left to the `upgrade` call itself, which also pays for the deployment.
+const UPGRADE_GAS: Gas = 20_000_000_000_000;
+
+pub fn state_version() -> u32 {
+    env::storage_read(STATE_VERSION_KEY)
+        .map(|bytes| u32::from_le_bytes(bytes[..].try_into().unwrap()))
+        .unwrap_or(0)
+}
+
+pub fn set_state_version(version: u32) {
+    env::storage_write(STATE_VERSION_KEY, &version.to_le_bytes());
+}
+
+/// Deploys `code` for the owner, see `deploy_and_migrate`. A failing migration reverts
+/// the deployment.
+pub fn upgrade(events: &EventStandard, owner: &AccountId, code: Vec<u8>) -> Promise {
+    admin::assert_owner(owner);
+    events.emit("upgrade", json!({ "code_sha256": code_hash(&code) }));
+    deploy_and_migrate(code)
+}
+
+/// Converts the stored state to `to_version` in `migrate`. `convert` reads the state
+/// stored with the given version, or returns `None` for a version it does not support.
+/// The caller must be the owner of the converted state or the contract account.
+pub fn migrate<T>(
+    events: &EventStandard,
+    to_version: u32,
+    convert: impl FnOnce(u32) -> Option<T>,
+    owner: impl FnOnce(&T) -> &AccountId,
+) -> T {
+    let from_version = state_version();
+    let contract = convert(from_version)
+        .unwrap_or_else(|| panic!("{}", Error::UnsupportedStateVersion { version: from_version }));
+    let predecessor = env::predecessor_account_id();
+    assert!(
+        &predecessor == owner(&contract) || predecessor == env::current_account_id(),
+        "{}", Error::NotAnOwner
+    );
+    set_state_version(to_version);
+    events.emit("migrate", json!({"from_version": from_version, "to_version": to_version}));
+    contract
+}
+
+/// Reads the contract state, as stored by the code being replaced.
+pub fn read_state<T: BorshDeserialize>() -> T {
+    env::state_read().unwrap_or_else(|| panic!("{}", Error::StateNotFound))
+}
+
+/// Deploys `code` to the current account and calls its `migrate` with the remaining gas.
+pub fn deploy_and_migrate(code: Vec<u8>) -> Promise {
+    let gas = (env::prepaid_gas() - env::used_gas()).saturating_sub(UPGRADE_GAS);
+    Promise::new(env::current_account_id())
+        .deploy_contract(code)
+        .function_call(b"migrate".to_vec(), vec![], 0, gas)
+}
+
+/// Hex-encoded sha256 of `code`, as listed in `res/manifest.json` by `cargo xtask build`.
+pub fn code_hash(code: &[u8]) -> String {
+    env::sha256(code).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -0,0 +1,121 @@
+//! Runs the contracts compiled to wasm in the NEAR runtime, so that tests reach the host
+//! functions that unit tests cannot call, such as `ecrecover`.
+//!
+//! Contracts run under the runtime config of protocol 83, the first to accept the
+//! reference types and bulk memory instructions that rustc emits for
+//! `wasm32-unknown-unknown` by default.
+
+use near_parameters::vm::{Config, VMKind};
+use near_parameters::{RuntimeConfigStore, RuntimeFeesConfig};
+use near_primitives_core::account::AccountContract;
+use near_primitives_core::types::{Balance, Gas};
+use near_vm_runner::logic::mocks::mock_external::MockedExternal;
+use near_vm_runner::logic::types::ReturnData;
+use near_vm_runner::logic::VMContext;
+use near_vm_runner::{ContractCode, MockContractRuntimeCache};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::{env, fs};
+
+const TARGET: &str = "wasm32-unknown-unknown";
+const PROTOCOL_VERSION: u32 = 83;
+
+pub const CONTRACT_ID: &str = "oracle.near";
+
+/// A contract deployed at `CONTRACT_ID`, whose state persists between calls.
+pub struct Contract {
+    ext: MockedExternal,
+    config: Arc<Config>,
+    fees: Arc<RuntimeFeesConfig>,
+    // Compiles the code once rather than on every call.
+    cache: MockContractRuntimeCache,
+}
+
+impl Contract {
+    /// Compiles `package` to wasm in release mode and deploys it.
+    pub fn build(package: &str) -> Self {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+        let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+        // A target directory of its own, as the one of the running tests stays locked.
+        let target_dir = target_directory(&cargo, root).join("wasm-tests");
+        let status = Command::new(&cargo)
+            .current_dir(root)
+            .args(["build", "--release", "--target", TARGET, "--package", package])
+            .arg("--target-dir")
+            .arg(&target_dir)
+            .status()
+            .expect("failed to run cargo");
+        assert!(status.success(), "cargo build failed with {}", status);
+
+        let file_name = format!("{}.wasm", package.replace('-', "_"));
+        let code = fs::read(target_dir.join(TARGET).join("release").join(file_name)).unwrap();
+
+        let store = RuntimeConfigStore::new(None);
+        let runtime_config = store.get_config(PROTOCOL_VERSION);
+        Self {
+            ext: MockedExternal::with_code(ContractCode::new(code, None)),
+            config: Arc::new(Config {
+                vm_kind: VMKind::Wasmtime,
+                ..Config::clone(&runtime_config.wasm_config)
+            }),
+            fees: Arc::new(RuntimeFeesConfig::clone(&runtime_config.fees)),
+            cache: MockContractRuntimeCache::default(),
+        }
+    }
+
+    /// Calls `method` from `caller` with the JSON `args`, returning what it returned or
+    /// why it failed.
+    pub fn call(&mut self, caller: &str, method: &str, args: Value) -> Result<Vec<u8>, String> {
+        let context = VMContext {
+            current_account_id: CONTRACT_ID.parse().unwrap(),
+            signer_account_id: caller.parse().unwrap(),
+            signer_account_pk: vec![0; 33],
+            predecessor_account_id: caller.parse().unwrap(),
+            refund_to_account_id: caller.parse().unwrap(),
+            input: Rc::from(serde_json::to_vec(&args).unwrap()),
+            promise_results: Vec::new().into(),
+            block_height: 10,
+            block_timestamp: 1_700_000_000_000_000_000,
+            epoch_height: 1,
+            account_balance: Balance::from_near(100),
+            account_locked_balance: Balance::ZERO,
+            storage_usage: 10u64.pow(6),
+            account_contract: AccountContract::None,
+            attached_deposit: Balance::ZERO,
+            prepaid_gas: Gas::from_teragas(300),
+            random_seed: vec![0; 32],
+            view_config: None,
+            output_data_receivers: vec![],
+        };
+
+        let gas_counter = context.make_gas_counter(&self.config);
+        let config = self.config.clone();
+        let prepared =
+            near_vm_runner::prepare(&self.ext, config, Some(&self.cache), gas_counter, method);
+        let outcome = near_vm_runner::run(prepared, &mut self.ext, &context, self.fees.clone())
+            .expect("the runtime failed");
+        if let Some(error) = outcome.aborted {
+            return Err(format!("{:?}", error));
+        }
+        match outcome.return_data {
+            ReturnData::Value(value) => Ok(value),
+            _ => Ok(vec![]),
+        }
+    }
+}
+
+// The directory cargo builds into, which honours `CARGO_TARGET_DIR` and
+// `build.target-dir`.
+fn target_directory(cargo: &str, root: &Path) -> PathBuf {
+    let output = Command::new(cargo)
+        .current_dir(root)
+        .args(["metadata", "--format-version", "1", "--no-deps"])
+        .output()
+        .expect("failed to run cargo metadata");
+    assert!(output.status.success(), "cargo metadata failed with {}", output.status);
+    let metadata: Value = serde_json::from_slice(&output.stdout).unwrap();
+    PathBuf::from(metadata["target_directory"].as_str().unwrap())
+}
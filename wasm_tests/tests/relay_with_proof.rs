@@ -0,0 +1,66 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+use wasm_tests::Contract;
+
+// Order of the secp256k1 group.
+const N: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+
+fn fixture(name: &str) -> Value {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../std_ref_basic/fixtures/proofs.json");
+    let fixtures: Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+    fixtures[name].clone()
+}
+
+fn set_up_bridge() -> Contract {
+    let mut contract = Contract::build("std-reference-basic");
+    contract.call("bob.near", "new", json!({})).unwrap();
+    let config = json!({"chain_id": fixture("chain_id"), "oracle_script_id": fixture("oracle_script_id")});
+    contract.call("bob.near", "set_bridge_config", json!({ "config": config })).unwrap();
+    let validators = json!({"validators": fixture("validators")});
+    contract.call("bob.near", "update_validator_powers", validators).unwrap();
+    for symbol in ["BTC", "ETH"] {
+        let info = json!({"symbol": symbol, "multiplier": "1000000000", "description": symbol});
+        contract.call("bob.near", "register_symbol", info).unwrap();
+    }
+    contract
+}
+
+#[test]
+fn test_relay_with_proof() {
+    let mut contract = set_up_bridge();
+
+    contract.call("carol.near", "relay_with_proof", json!({"proof": fixture("btc_eth")})).unwrap();
+    let btc = contract.call("carol.near", "get_refs", json!({"symbol": "BTC"})).unwrap();
+    assert_eq!(
+        json!([20_000_123_456_000u64, 1_650_000_010_000_000_000u64, 1001]),
+        serde_json::from_slice::<Value>(&btc).unwrap()
+    );
+}
+
+#[test]
+fn test_relay_with_proof_fail_because_high_s() {
+    let mut contract = set_up_bridge();
+    let mut proof = fixture("btc_eth");
+
+    // the same signature with s replaced by n - s, which `ecrecover` refuses
+    let signature = &mut proof["block_relay_proof"]["signatures"][0];
+    let s = STANDARD.decode(signature["s"].as_str().unwrap()).unwrap();
+    let mut high_s = [0u8; 32];
+    let mut borrow = 0;
+    for i in (0..32).rev() {
+        let digit = N[i] as i16 - s[i] as i16 - borrow;
+        high_s[i] = digit.rem_euclid(256) as u8;
+        borrow = (digit < 0) as i16;
+    }
+    signature["s"] = json!(STANDARD.encode(high_s));
+    signature["v"] = json!(55 - signature["v"].as_u64().unwrap());
+
+    let error = contract.call("carol.near", "relay_with_proof", json!({ "proof": proof }));
+    assert!(error.unwrap_err().contains("INVALID_SIGNATURE"));
+}
@@ -0,0 +1,200 @@
+//! Repository tasks, run with `cargo xtask <task>`.
+//!
+//! `build` compiles every contract to wasm, copies the binaries to `res/` and writes
+//! `res/manifest.json` with the sha256 hash and the exported methods of each binary.
+
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+use std::{env, fs};
+
+const TARGET: &str = "wasm32-unknown-unknown";
+
+// (package name, wasm file stem)
+const CONTRACTS: &[(&str, &str)] = &[
+    ("std-reference-basic", "std_reference_basic"),
+    ("std-proxy", "std_proxy"),
+    ("simple-price-db", "simple_price_db"),
+];
+
+fn main() {
+    let task = env::args().nth(1);
+    let result = match task.as_deref() {
+        Some("build") => build(),
+        _ => {
+            eprintln!("Usage: cargo xtask build");
+            process::exit(1);
+        }
+    };
+    if let Err(err) = result {
+        eprintln!("xtask: {}", err);
+        process::exit(1);
+    }
+}
+
+fn project_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap().to_path_buf()
+}
+
+fn build() -> Result<(), String> {
+    let root = project_root();
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+
+    let mut command = Command::new(&cargo);
+    command.current_dir(&root).args(["build", "--release", "--target", TARGET]);
+    for (package, _) in CONTRACTS {
+        command.args(["--package", package]);
+    }
+    let status = command.status().map_err(|err| format!("failed to run cargo: {}", err))?;
+    if !status.success() {
+        return Err(format!("cargo build failed with {}", status));
+    }
+
+    let target_dir = target_directory(&cargo, &root)?;
+    let out_dir = root.join("res");
+    fs::create_dir_all(&out_dir).map_err(|err| format!("failed to create res/: {}", err))?;
+
+    let mut manifest = vec![];
+    for (package, stem) in CONTRACTS {
+        let file_name = format!("{}.wasm", stem);
+        let wasm_path = target_dir.join(TARGET).join("release").join(&file_name);
+        let code = fs::read(&wasm_path)
+            .map_err(|err| format!("failed to read {}: {}", wasm_path.display(), err))?;
+        let methods = exported_functions(&code)
+            .map_err(|err| format!("failed to parse {}: {}", wasm_path.display(), err))?;
+        fs::write(out_dir.join(&file_name), &code)
+            .map_err(|err| format!("failed to write {}: {}", file_name, err))?;
+
+        manifest.push(json!({
+            "package": package,
+            "file": file_name,
+            "sha256": format!("{:x}", Sha256::digest(&code)),
+            "methods": methods,
+        }));
+        println!("res/{}", file_name);
+    }
+
+    let manifest = serde_json::to_string_pretty(&manifest).unwrap();
+    fs::write(out_dir.join("manifest.json"), manifest + "\n")
+        .map_err(|err| format!("failed to write manifest.json: {}", err))?;
+    println!("res/manifest.json");
+    Ok(())
+}
+
+/// Returns the directory cargo builds into, which honours `CARGO_TARGET_DIR` and
+/// `build.target-dir`.
+fn target_directory(cargo: &str, root: &Path) -> Result<PathBuf, String> {
+    let output = Command::new(cargo)
+        .current_dir(root)
+        .args(["metadata", "--format-version", "1", "--no-deps"])
+        .output()
+        .map_err(|err| format!("failed to run cargo metadata: {}", err))?;
+    if !output.status.success() {
+        return Err(format!("cargo metadata failed with {}", output.status));
+    }
+    let metadata: serde_json::Value = serde_json::from_slice(&output.stdout)
+        .map_err(|err| format!("failed to parse cargo metadata: {}", err))?;
+    metadata["target_directory"]
+        .as_str()
+        .map(PathBuf::from)
+        .ok_or_else(|| "cargo metadata has no target_directory".to_string())
+}
+
+/// Returns the names of the functions in the export section of a wasm module, sorted.
+fn exported_functions(code: &[u8]) -> Result<Vec<String>, String> {
+    const EXPORT_SECTION: u8 = 7;
+    const FUNCTION_EXPORT: u8 = 0;
+
+    if code.len() < 8 || &code[..4] != b"\0asm" {
+        return Err("not a wasm module".to_string());
+    }
+    let mut reader = Reader { code, pos: 8 };
+    let mut names = vec![];
+    while reader.pos < code.len() {
+        let id = reader.byte()?;
+        let size = reader.leb128()? as usize;
+        let end = reader.pos + size;
+        if id == EXPORT_SECTION {
+            for _ in 0..reader.leb128()? {
+                let len = reader.leb128()? as usize;
+                let name = reader.bytes(len)?;
+                let kind = reader.byte()?;
+                reader.leb128()?;
+                if kind == FUNCTION_EXPORT {
+                    names.push(String::from_utf8(name.to_vec()).map_err(|err| err.to_string())?);
+                }
+            }
+        }
+        reader.pos = end;
+    }
+    names.sort();
+    Ok(names)
+}
+
+struct Reader<'a> {
+    code: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let bytes = self.code.get(self.pos..self.pos + len).ok_or("unexpected end of module")?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn leb128(&mut self) -> Result<u64, String> {
+        let mut result = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            result |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+        }
+        Err("invalid leb128".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module(sections: &[(u8, Vec<u8>)]) -> Vec<u8> {
+        let mut code = b"\0asm\x01\0\0\0".to_vec();
+        for (id, body) in sections {
+            code.push(*id);
+            code.push(body.len() as u8);
+            code.extend(body);
+        }
+        code
+    }
+
+    #[test]
+    fn test_exported_functions() {
+        let exports = vec![
+            3, // number of exports
+            5, b'r', b'e', b'l', b'a', b'y', 0, 1, // function
+            6, b'm', b'e', b'm', b'o', b'r', b'y', 2, 0, // memory
+            3, b'n', b'e', b'w', 0, 0, // function
+        ];
+        let code = module(&[(1, vec![0]), (7, exports), (10, vec![0])]);
+        assert_eq!(Ok(vec!["new".to_string(), "relay".to_string()]), exported_functions(&code));
+    }
+
+    #[test]
+    fn test_exported_functions_without_export_section() {
+        assert_eq!(Ok(vec![]), exported_functions(&module(&[(1, vec![0])])));
+    }
+
+    #[test]
+    fn test_exported_functions_invalid_module() {
+        assert!(exported_functions(b"not wasm").is_err());
+        assert!(exported_functions(&module(&[(7, vec![1, 5, b'r'])])).is_err());
+    }
+}